@@ -0,0 +1,121 @@
+use std::fmt::Display;
+
+use crate::Matrix2D;
+
+/// Cells flowing together, walled off by cells at least as high as the wall
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Basin {
+    pub label: usize,
+    /// Lowest cell, the first one in reading order on ties
+    pub low_point: (usize, usize),
+    /// Cells as `(x, y)`, in the order they were reached
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Basin {
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// Every basin of a height map, along with the basin label of each cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasinMap {
+    /// Basin label of each cell, `None` for walls
+    pub labels: Matrix2D<Option<usize>>,
+    /// Basins, by label
+    pub basins: Vec<Basin>,
+}
+
+impl BasinMap {
+    /// Label every basin of `heights`, cells of height `wall` and above being walls.
+    ///
+    /// Basins are labelled in reading order of their first cell.
+    #[must_use]
+    pub fn new(heights: &Matrix2D<usize>, wall: usize) -> Self {
+        let mut labels = Matrix2D {
+            width: heights.width,
+            height: heights.height,
+            values: vec![None; heights.values.len()],
+        };
+        let basins: Vec<_> = heights
+            .connected_components(false, |&height| height < wall)
+            .into_iter()
+            .enumerate()
+            .map(|(label, component)| {
+                for &(x, y) in &component.cells {
+                    labels.values[x + y * heights.width] = Some(label);
+                }
+                let low_point = component
+                    .cells
+                    .iter()
+                    .copied()
+                    .min_by_key(|&(x, y)| (heights.get_x_y(x, y), y, x))
+                    .unwrap_or(component.min);
+                Basin {
+                    label,
+                    low_point,
+                    cells: component.cells,
+                }
+            })
+            .collect();
+
+        Self { labels, basins }
+    }
+
+    /// Sizes of the basins, largest first
+    #[must_use]
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes: Vec<_> = self.basins.iter().map(Basin::size).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+}
+
+impl Display for BasinMap {
+    /// One letter per basin, cycling through `a` to `z`, and `#` for walls
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.labels.values.chunks(self.labels.width.max(1)) {
+            for label in row {
+                let c = label.map_or('#', |label| {
+                    char::from(b'a' + u8::try_from(label % 26).unwrap_or(0))
+                });
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn heights(input: &str) -> Matrix2D<usize> {
+        Matrix2D::from_digits(input.lines()).unwrap()
+    }
+
+    #[test]
+    fn test_basins() {
+        let heights = heights("2199943210\n3987894921\n9856789892\n8767896789\n9899965678");
+        let copy = heights.clone();
+        let map = BasinMap::new(&heights, 9);
+        assert_eq!(heights, copy);
+
+        assert_eq!(map.sizes(), vec![14, 9, 9, 3]);
+        let low_points: Vec<_> = map.basins.iter().map(|basin| basin.low_point).collect();
+        assert_eq!(low_points, vec![(1, 0), (9, 0), (2, 2), (6, 4)]);
+        assert_eq!(map.labels.get_x_y(0, 0), Some(0));
+        assert_eq!(map.labels.get_x_y(2, 0), None);
+        assert_eq!(
+            map.to_string(),
+            "aa###bbbbb\na#ccc#b#bb\n#ccccc#d#b\nccccc#ddd#\n#c###ddddd\n"
+        );
+
+        // A higher wall merges basins
+        assert_eq!(BasinMap::new(&heights, 10).sizes(), vec![50]);
+        assert_eq!(BasinMap::new(&heights, 0).basins, vec![]);
+    }
+}
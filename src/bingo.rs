@@ -0,0 +1,399 @@
+use hashbrown::{HashMap, HashSet};
+use thiserror::Error;
+
+use crate::Matrix2D;
+
+/// Errors raised while parsing a bingo game.
+///
+/// Lines are 1-based; cards are numbered from 0 like in `BingoEvent`.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum BingoError {
+    #[error("line 1: empty draw line")]
+    EmptyDraw,
+    #[error("line 1: invalid draw `{token}`")]
+    InvalidDraw { token: String },
+    #[error("card {card}, line {line}: invalid number `{token}`")]
+    InvalidNumber {
+        card: usize,
+        line: usize,
+        token: String,
+    },
+    #[error("card {card}, line {line}: expected {expected} numbers, found {found}")]
+    RaggedRow {
+        card: usize,
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("card {card}, line {line}: card is {width}x{height}, not square")]
+    NotSquare {
+        card: usize,
+        line: usize,
+        width: usize,
+        height: usize,
+    },
+    #[error("card {card}, line {line}: duplicate number {value}")]
+    DuplicateNumber {
+        card: usize,
+        line: usize,
+        value: usize,
+    },
+}
+
+/// Parse a draw line followed by blank-line separated square cards
+///
+/// # Errors
+///
+/// Fails on an empty or invalid draw line, on non-numeric tokens, and on
+/// ragged, non-square or duplicate-holding cards
+pub fn parse_game(input: &str) -> Result<(Vec<usize>, Vec<Matrix2D<usize>>), BingoError> {
+    let mut lines = input.lines().enumerate();
+
+    let draw_line = lines.next().map_or("", |(_, line)| line.trim());
+    if draw_line.is_empty() {
+        return Err(BingoError::EmptyDraw);
+    }
+    let draws = draw_line
+        .split(',')
+        .map(|token| {
+            token.trim().parse().map_err(|_| BingoError::InvalidDraw {
+                token: token.trim().to_string(),
+            })
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+
+    // Group the remaining lines into cards, keeping their line numbers
+    let mut blocks: Vec<Vec<(usize, &str)>> = Vec::new();
+    let mut current = Vec::new();
+    for (idx, line) in lines {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push((idx + 1, line));
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+
+    let cards = blocks
+        .iter()
+        .enumerate()
+        .map(|(card, block)| parse_card(card, block))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((draws, cards))
+}
+
+fn parse_card(card: usize, block: &[(usize, &str)]) -> Result<Matrix2D<usize>, BingoError> {
+    let first_line = block.first().map_or(0, |&(line, _)| line);
+    let height = block.len();
+    let mut width = None;
+    let mut values = Vec::new();
+    let mut seen = HashSet::new();
+
+    for &(line, content) in block {
+        let row = content
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .parse::<usize>()
+                    .map_err(|_| BingoError::InvalidNumber {
+                        card,
+                        line,
+                        token: token.to_string(),
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let expected = *width.get_or_insert(row.len());
+        if row.len() != expected {
+            return Err(BingoError::RaggedRow {
+                card,
+                line,
+                expected,
+                found: row.len(),
+            });
+        }
+        for &value in &row {
+            if !seen.insert(value) {
+                return Err(BingoError::DuplicateNumber { card, line, value });
+            }
+        }
+        values.extend(row);
+    }
+
+    let width = width.unwrap_or(0);
+    if width != height {
+        return Err(BingoError::NotSquare {
+            card,
+            line: first_line,
+            width,
+            height,
+        });
+    }
+
+    Ok(Matrix2D {
+        width,
+        height,
+        values,
+    })
+}
+
+/// Something that happened while drawing numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BingoEvent {
+    /// `card` completed a line when `draw` was called.
+    /// `score` is the sum of its unmarked numbers times `draw`.
+    CardWon {
+        card: usize,
+        draw: usize,
+        score: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Card {
+    width: usize,
+    height: usize,
+    marked: Vec<bool>,
+    // Marked cells per row, per column and per diagonal
+    rows: Vec<usize>,
+    cols: Vec<usize>,
+    diags: [usize; 2],
+    unmarked_sum: usize,
+    won: bool,
+}
+
+impl Card {
+    fn new(matrix: &Matrix2D<usize>) -> Self {
+        Self {
+            width: matrix.width,
+            height: matrix.height,
+            marked: vec![false; matrix.values.len()],
+            rows: vec![0; matrix.height],
+            cols: vec![0; matrix.width],
+            diags: [0; 2],
+            unmarked_sum: matrix.values.iter().sum(),
+            won: false,
+        }
+    }
+
+    /// Mark a cell, returning whether it completes a line
+    fn mark(&mut self, cell: usize, value: usize, diagonals: bool) -> bool {
+        if self.marked[cell] {
+            return false;
+        }
+        self.marked[cell] = true;
+        self.unmarked_sum -= value;
+
+        let (x, y) = (cell % self.width, cell / self.width);
+        self.rows[y] += 1;
+        self.cols[x] += 1;
+        let mut complete = self.rows[y] == self.width || self.cols[x] == self.height;
+
+        if diagonals && self.width == self.height {
+            let size = self.width;
+            if x == y {
+                self.diags[0] += 1;
+                complete |= self.diags[0] == size;
+            }
+            if x + y == size - 1 {
+                self.diags[1] += 1;
+                complete |= self.diags[1] == size;
+            }
+        }
+        complete
+    }
+}
+
+/// A bingo game over any number of cards of any size.
+///
+/// Every number is indexed to the cells holding it, so a draw only touches
+/// the cells it marks.
+#[derive(Debug, Clone)]
+pub struct BingoGame {
+    cards: Vec<Card>,
+    index: HashMap<usize, Vec<(usize, usize)>>,
+    diagonals: bool,
+}
+
+impl BingoGame {
+    #[must_use]
+    pub fn new(cards: &[Matrix2D<usize>]) -> Self {
+        let mut index: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        for (card, matrix) in cards.iter().enumerate() {
+            for (cell, &value) in matrix.values.iter().enumerate() {
+                index.entry(value).or_default().push((card, cell));
+            }
+        }
+
+        Self {
+            cards: cards.iter().map(Card::new).collect(),
+            index,
+            diagonals: false,
+        }
+    }
+
+    /// Also count full diagonals as wins, on square cards only
+    #[must_use]
+    pub fn with_diagonals(self, diagonals: bool) -> Self {
+        Self { diagonals, ..self }
+    }
+
+    /// Mark `number` on every card, returning the cards that won with it, in card order
+    pub fn draw(&mut self, number: usize) -> Vec<BingoEvent> {
+        let mut events = Vec::new();
+        let Some(cells) = self.index.get(&number) else {
+            return events;
+        };
+
+        for &(card_idx, cell) in cells {
+            let card = &mut self.cards[card_idx];
+            if card.won {
+                continue;
+            }
+            if card.mark(cell, number, self.diagonals) {
+                card.won = true;
+                events.push(BingoEvent::CardWon {
+                    card: card_idx,
+                    draw: number,
+                    score: card.unmarked_sum * number,
+                });
+            }
+        }
+        events
+    }
+
+    /// Lazily play `draws`, yielding wins in the order they happen
+    pub fn play<'a>(&'a mut self, draws: &'a [usize]) -> impl Iterator<Item = BingoEvent> + 'a {
+        draws.iter().flat_map(move |&number| self.draw(number))
+    }
+
+    /// Number of cards that have not won yet
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.cards.iter().filter(|card| !card.won).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(size: usize, values: &[usize]) -> Matrix2D<usize> {
+        Matrix2D {
+            width: size,
+            height: values.len() / size,
+            values: values.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_win_order() {
+        let cards = [
+            card(2, &[1, 2, 3, 4]),
+            card(2, &[5, 6, 7, 8]),
+            card(3, &[1, 5, 9, 2, 6, 10, 3, 7, 11]),
+        ];
+        let mut game = BingoGame::new(&cards);
+        let events: Vec<_> = game.play(&[1, 5, 3, 7, 6, 2, 10]).collect();
+
+        assert_eq!(
+            events,
+            vec![
+                BingoEvent::CardWon {
+                    card: 0,
+                    draw: 3,
+                    score: 6 * 3
+                },
+                BingoEvent::CardWon {
+                    card: 1,
+                    draw: 7,
+                    score: 14 * 7
+                },
+                BingoEvent::CardWon {
+                    card: 2,
+                    draw: 6,
+                    score: (9 + 2 + 10 + 11) * 6
+                },
+            ]
+        );
+        assert_eq!(game.remaining(), 0);
+    }
+
+    #[test]
+    fn test_parse_game() {
+        let (draws, cards) = parse_game("7,4,9\n\n1 2\n 3 4\n\n\n5 6\n7 8\n\n").unwrap();
+        assert_eq!(draws, vec![7, 4, 9]);
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[1].values, vec![5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_game("\n1 2\n3 4"), Err(BingoError::EmptyDraw));
+        assert_eq!(
+            parse_game("1,x"),
+            Err(BingoError::InvalidDraw {
+                token: "x".to_string()
+            })
+        );
+        assert_eq!(
+            parse_game("1\n\n1 2\n3 4\n\n1 2\n3 y"),
+            Err(BingoError::InvalidNumber {
+                card: 1,
+                line: 7,
+                token: "y".to_string()
+            })
+        );
+        assert_eq!(
+            parse_game("1\n\n1 2\n3 4 5"),
+            Err(BingoError::RaggedRow {
+                card: 0,
+                line: 4,
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            parse_game("1\n\n1 2\n3 4\n5 6"),
+            Err(BingoError::NotSquare {
+                card: 0,
+                line: 3,
+                width: 2,
+                height: 3
+            })
+        );
+        assert_eq!(
+            parse_game("1\n\n1 2\n3 1"),
+            Err(BingoError::DuplicateNumber {
+                card: 0,
+                line: 4,
+                value: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let cards = [card(3, &[1, 2, 3, 4, 5, 6, 7, 8, 9])];
+        let draws = [3, 5, 7];
+
+        assert_eq!(BingoGame::new(&cards).play(&draws).next(), None);
+        assert_eq!(
+            BingoGame::new(&cards)
+                .with_diagonals(true)
+                .play(&draws)
+                .next(),
+            Some(BingoEvent::CardWon {
+                card: 0,
+                draw: 7,
+                score: 30 * 7
+            })
+        );
+    }
+}
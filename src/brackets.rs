@@ -0,0 +1,541 @@
+use thiserror::Error;
+
+/// Errors raised while checking a line. Columns are 1-based.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GrammarError {
+    #[error("column {column}: unknown character `{found}`")]
+    UnknownChar { column: usize, found: char },
+}
+
+/// Errors raised while scoring a whole set of lines
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScoreError {
+    #[error(transparent)]
+    Grammar(#[from] GrammarError),
+    #[error("{0} incomplete lines, a middle score needs an odd count")]
+    EvenIncompleteCount(usize),
+    #[error("completion score overflow")]
+    Overflow,
+}
+
+/// Errors raised while adding a pair to a grammar
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PairError {
+    #[error("`{0}` cannot both open and close a pair")]
+    SameOpenClose(char),
+    #[error("`{0}` is already used by another pair")]
+    SharedChar(char),
+}
+
+/// An opener and its closer, with their scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pair {
+    pub open: char,
+    pub close: char,
+    /// Score of a line corrupted by this closer
+    pub corrupted_score: u64,
+    /// Score of this closer in a completion string
+    pub completion_score: u64,
+}
+
+/// An opener and its (1-based) column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opener {
+    pub column: usize,
+    pub open: char,
+}
+
+/// First closer not matching the innermost opener. Columns are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Corruption {
+    pub column: usize,
+    pub found: char,
+    /// Closer of the innermost opener, `None` if nothing was open
+    pub expected: Option<char>,
+    /// Innermost opener, the one `found` fails to match
+    pub opener: Option<Opener>,
+}
+
+/// State of a checked line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LineStatus {
+    Valid,
+    /// Openers left without a closer, innermost last
+    Incomplete(Vec<Opener>),
+    Corrupted(Corruption),
+}
+
+/// Smallest edit making a line valid or incomplete. Columns are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// The line is already valid
+    Nothing,
+    /// Append the missing closers
+    Append(String),
+    Replace {
+        column: usize,
+        with: char,
+    },
+    Delete {
+        column: usize,
+    },
+    Insert {
+        column: usize,
+        with: char,
+    },
+}
+
+impl Repair {
+    /// The line once repaired, `None` if the column to edit is not in `line`
+    #[must_use]
+    pub fn apply(&self, line: &str) -> Option<String> {
+        let mut chars: Vec<char> = line.chars().collect();
+        match *self {
+            Self::Nothing => {}
+            Self::Append(ref closers) => chars.extend(closers.chars()),
+            Self::Replace { column, with } => *chars.get_mut(column.checked_sub(1)?)? = with,
+            Self::Delete { column } => {
+                let idx = column.checked_sub(1).filter(|&idx| idx < chars.len())?;
+                chars.remove(idx);
+            }
+            Self::Insert { column, with } => {
+                let idx = column.checked_sub(1).filter(|&idx| idx <= chars.len())?;
+                chars.insert(idx, with);
+            }
+        }
+        Some(chars.into_iter().collect())
+    }
+}
+
+/// Bracket pairs, ignored characters and score tables of a checker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketGrammar {
+    pairs: Vec<Pair>,
+    ignored: Vec<char>,
+    completion_base: u128,
+}
+
+impl BracketGrammar {
+    /// A grammar without any pair, completion scores growing by a factor of 5
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pairs: Vec::new(),
+            ignored: Vec::new(),
+            completion_base: 5,
+        }
+    }
+
+    /// The navigation subsystem: `()`, `[]`, `{}` and `<>`
+    #[must_use]
+    pub fn navigation() -> Self {
+        let pairs = [
+            ('(', ')', 3, 1),
+            ('[', ']', 57, 2),
+            ('{', '}', 1197, 3),
+            ('<', '>', 25137, 4),
+        ]
+        .into_iter()
+        .map(|(open, close, corrupted_score, completion_score)| Pair {
+            open,
+            close,
+            corrupted_score,
+            completion_score,
+        })
+        .collect();
+        Self {
+            pairs,
+            ..Self::new()
+        }
+    }
+
+    /// Add a pair
+    ///
+    /// # Errors
+    ///
+    /// Fails if the pair opens and closes with the same character, or uses a
+    /// character of another pair
+    pub fn with_pair(mut self, pair: Pair) -> Result<Self, PairError> {
+        if pair.open == pair.close {
+            return Err(PairError::SameOpenClose(pair.open));
+        }
+        for c in [pair.open, pair.close] {
+            if self
+                .pairs
+                .iter()
+                .any(|other| other.open == c || other.close == c)
+            {
+                return Err(PairError::SharedChar(c));
+            }
+        }
+        self.pairs.push(pair);
+        Ok(self)
+    }
+
+    /// Skip these characters instead of reporting them as unknown
+    #[must_use]
+    pub fn with_ignored(mut self, ignored: &str) -> Self {
+        self.ignored.extend(ignored.chars());
+        self
+    }
+
+    /// Factor applied to a completion score before adding the next closer
+    #[must_use]
+    pub fn with_completion_base(self, completion_base: u128) -> Self {
+        Self {
+            completion_base,
+            ..self
+        }
+    }
+
+    fn opened_by(&self, open: char) -> Option<&Pair> {
+        self.pairs.iter().find(|pair| pair.open == open)
+    }
+
+    fn closed_by(&self, close: char) -> Option<&Pair> {
+        self.pairs.iter().find(|pair| pair.close == close)
+    }
+
+    /// Check a line, stopping at the first corrupted closer
+    ///
+    /// # Errors
+    ///
+    /// Fails on a character that is neither an opener, a closer nor ignored
+    pub fn check(&self, line: &str) -> Result<LineStatus, GrammarError> {
+        let mut stack: Vec<Opener> = Vec::new();
+
+        for (idx, c) in line.chars().enumerate() {
+            if self.opened_by(c).is_some() {
+                stack.push(Opener {
+                    column: idx + 1,
+                    open: c,
+                });
+            } else if let Some(pair) = self.closed_by(c) {
+                let opener = stack.pop();
+                if opener.map(|opener| opener.open) != Some(pair.open) {
+                    return Ok(LineStatus::Corrupted(Corruption {
+                        column: idx + 1,
+                        found: c,
+                        expected: opener
+                            .and_then(|opener| self.opened_by(opener.open))
+                            .map(|pair| pair.close),
+                        opener,
+                    }));
+                }
+            } else if !self.ignored.contains(&c) {
+                return Err(GrammarError::UnknownChar {
+                    column: idx + 1,
+                    found: c,
+                });
+            }
+        }
+
+        if stack.is_empty() {
+            Ok(LineStatus::Valid)
+        } else {
+            Ok(LineStatus::Incomplete(stack))
+        }
+    }
+
+    /// Score of a line corrupted by `close`
+    #[must_use]
+    pub fn corrupted_score(&self, close: char) -> Option<u64> {
+        self.closed_by(close).map(|pair| pair.corrupted_score)
+    }
+
+    /// Score of the closers completing `openers`, innermost opener last.
+    /// `None` if it overflows.
+    #[must_use]
+    pub fn completion_score(&self, openers: &[Opener]) -> Option<u128> {
+        openers.iter().rev().try_fold(0_u128, |acc, opener| {
+            let score = self
+                .opened_by(opener.open)
+                .map_or(0, |pair| u128::from(pair.completion_score));
+            acc.checked_mul(self.completion_base)?.checked_add(score)
+        })
+    }
+
+    /// Closers completing `openers`, innermost opener last
+    #[must_use]
+    pub fn completion(&self, openers: &[Opener]) -> String {
+        openers
+            .iter()
+            .rev()
+            .filter_map(|opener| self.opened_by(opener.open).map(|pair| pair.close))
+            .collect()
+    }
+
+    /// Smallest edit fixing the first problem of a line.
+    ///
+    /// Incomplete lines get their missing closers. For a corrupted line, the
+    /// bad closer is replaced by the expected one, deleted, or the expected
+    /// closer is inserted before it: the first of these edits leaving the line
+    /// uncorrupted wins, else the one pushing the next corruption furthest.
+    ///
+    /// # Errors
+    ///
+    /// Fails on a character that is neither an opener, a closer nor ignored
+    pub fn repair(&self, line: &str) -> Result<Repair, GrammarError> {
+        let corruption = match self.check(line)? {
+            LineStatus::Valid => return Ok(Repair::Nothing),
+            LineStatus::Incomplete(openers) => {
+                return Ok(Repair::Append(self.completion(&openers)))
+            }
+            LineStatus::Corrupted(corruption) => corruption,
+        };
+
+        let column = corruption.column;
+        let mut candidates = Vec::new();
+        if let Some(with) = corruption.expected {
+            candidates.push(Repair::Replace { column, with });
+        }
+        candidates.push(Repair::Delete { column });
+        if let Some(with) = corruption.expected {
+            candidates.push(Repair::Insert { column, with });
+        }
+
+        let mut best: Option<(usize, Repair)> = None;
+        for candidate in candidates {
+            // How far the repaired line goes before getting corrupted
+            let Some(repaired) = candidate.apply(line) else {
+                continue;
+            };
+            let reach = match self.check(&repaired)? {
+                LineStatus::Corrupted(next) => next.column,
+                _ => return Ok(candidate),
+            };
+            if best.as_ref().is_none_or(|(best, _)| reach > *best) {
+                best = Some((reach, candidate));
+            }
+        }
+        Ok(best.map_or(Repair::Delete { column }, |(_, repair)| repair))
+    }
+
+    /// The line followed by caret markers under its first problem
+    ///
+    /// ```text
+    /// {([(<{}[<>[]}>{[]{[(<()>
+    ///        ^    ^ expected `]`, found `}`
+    /// ```
+    #[must_use]
+    pub fn annotate(&self, line: &str) -> String {
+        let (carets, message) = match self.check(line) {
+            Ok(LineStatus::Valid) => (Vec::new(), "valid".to_string()),
+            Ok(LineStatus::Incomplete(openers)) => (
+                openers.iter().map(|opener| opener.column).collect(),
+                format!("incomplete, missing `{}`", self.completion(&openers)),
+            ),
+            Ok(LineStatus::Corrupted(corruption)) => {
+                let message = match corruption.expected {
+                    Some(expected) => {
+                        format!("expected `{expected}`, found `{}`", corruption.found)
+                    }
+                    None => format!("unexpected `{}`, nothing is open", corruption.found),
+                };
+                let mut carets: Vec<_> = corruption.opener.map(|o| o.column).into_iter().collect();
+                carets.push(corruption.column);
+                (carets, message)
+            }
+            Err(err @ GrammarError::UnknownChar { column, .. }) => (vec![column], err.to_string()),
+        };
+
+        let mut marker = String::new();
+        for column in carets {
+            while marker.chars().count() + 1 < column {
+                marker.push(' ');
+            }
+            marker.push('^');
+        }
+        if !marker.is_empty() {
+            marker.push(' ');
+        }
+        format!("{line}\n{marker}{message}")
+    }
+}
+
+impl Default for BracketGrammar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navigation() {
+        let grammar = BracketGrammar::navigation();
+        assert_eq!(grammar.check("[<>({}){}[([])<>]]"), Ok(LineStatus::Valid));
+        assert_eq!(
+            grammar.check("{([(<{}[<>[]}>{[]{[(<()>"),
+            Ok(LineStatus::Corrupted(Corruption {
+                column: 13,
+                found: '}',
+                expected: Some(']'),
+                opener: Some(Opener {
+                    column: 8,
+                    open: '['
+                })
+            }))
+        );
+        let Ok(LineStatus::Incomplete(openers)) = grammar.check("[({(<(())[]>[[{[]{<()<>>") else {
+            panic!("line should be incomplete");
+        };
+        assert_eq!(grammar.completion(&openers), "}}]])})]");
+        assert_eq!(grammar.completion_score(&openers), Some(288_957));
+        let deep = "(".repeat(60);
+        let Ok(LineStatus::Incomplete(openers)) = grammar.check(&deep) else {
+            panic!("line should be incomplete");
+        };
+        assert_eq!(grammar.completion_score(&openers), None);
+        assert_eq!(grammar.corrupted_score('>'), Some(25137));
+        assert_eq!(
+            grammar.check("(a)"),
+            Err(GrammarError::UnknownChar {
+                column: 2,
+                found: 'a'
+            })
+        );
+    }
+
+    #[test]
+    fn test_custom_grammar() {
+        let grammar = BracketGrammar::new()
+            .with_pair(Pair {
+                open: '«',
+                close: '»',
+                corrupted_score: 10,
+                completion_score: 1,
+            })
+            .unwrap()
+            .with_pair(Pair {
+                open: 'b',
+                close: 'e',
+                corrupted_score: 20,
+                completion_score: 2,
+            })
+            .unwrap()
+            .with_ignored(" x")
+            .with_completion_base(3);
+
+        assert_eq!(grammar.check("b «x» e"), Ok(LineStatus::Valid));
+        let Ok(LineStatus::Corrupted(corruption)) = grammar.check("«b»") else {
+            panic!("line should be corrupted");
+        };
+        assert_eq!(corruption.expected, Some('e'));
+        let Ok(LineStatus::Incomplete(openers)) = grammar.check("b « b") else {
+            panic!("line should be incomplete");
+        };
+        let columns: Vec<_> = openers.iter().map(|opener| opener.column).collect();
+        assert_eq!(columns, vec![1, 3, 5]);
+        assert_eq!(grammar.completion(&openers), "e»e");
+        assert_eq!(
+            grammar.completion_score(&openers),
+            Some((2 * 3 + 1) * 3 + 2)
+        );
+        assert_eq!(
+            grammar.check("(x)"),
+            Err(GrammarError::UnknownChar {
+                column: 1,
+                found: '('
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_pairs() {
+        let pair = |open, close| Pair {
+            open,
+            close,
+            corrupted_score: 1,
+            completion_score: 1,
+        };
+        assert_eq!(
+            BracketGrammar::new().with_pair(pair('|', '|')),
+            Err(PairError::SameOpenClose('|'))
+        );
+        assert_eq!(
+            BracketGrammar::navigation().with_pair(pair('>', '<')),
+            Err(PairError::SharedChar('>'))
+        );
+        assert_eq!(
+            BracketGrammar::navigation().with_pair(pair('/', ')')),
+            Err(PairError::SharedChar(')'))
+        );
+    }
+
+    #[test]
+    fn test_repair() {
+        let grammar = BracketGrammar::navigation();
+        let repair = |line: &str| grammar.repair(line).unwrap();
+
+        assert_eq!(repair("[()]"), Repair::Nothing);
+        assert_eq!(repair("[(<"), Repair::Append(">)]".to_string()));
+        // A wrong closer is replaced
+        assert_eq!(
+            repair("[(]]"),
+            Repair::Replace {
+                column: 3,
+                with: ')'
+            }
+        );
+        // A stray closer is deleted
+        assert_eq!(repair("[])"), Repair::Delete { column: 3 });
+        assert_eq!(repair("[)]"), Repair::Delete { column: 2 });
+        // When no edit is enough, the one going furthest wins
+        assert_eq!(
+            repair("{(}]"),
+            Repair::Insert {
+                column: 3,
+                with: ')'
+            }
+        );
+        for line in ["[(]]", "[])", "[)]", "[(]", "{([(<{}[<>[]}>{[]{[(<()>"] {
+            let fixed = repair(line).apply(line).unwrap();
+            assert!(
+                !matches!(grammar.check(&fixed), Ok(LineStatus::Corrupted(_))),
+                "{line} -> {fixed}"
+            );
+        }
+        assert_eq!(Repair::Delete { column: 4 }.apply("[)]"), None);
+        assert_eq!(
+            Repair::Replace {
+                column: 0,
+                with: ')'
+            }
+            .apply("[)]"),
+            None
+        );
+        assert_eq!(
+            Repair::Insert {
+                column: 4,
+                with: ']'
+            }
+            .apply("[()"),
+            Some("[()]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let grammar = BracketGrammar::navigation();
+        assert_eq!(
+            grammar.annotate("{([(<{}[<>[]}>{[]{[(<()>"),
+            "{([(<{}[<>[]}>{[]{[(<()>\n       ^    ^ expected `]`, found `}`"
+        );
+        assert_eq!(
+            grammar.annotate("(<>["),
+            "(<>[\n^  ^ incomplete, missing `])`"
+        );
+        assert_eq!(
+            grammar.annotate("())"),
+            "())\n  ^ unexpected `)`, nothing is open"
+        );
+        assert_eq!(
+            grammar.annotate("(x)"),
+            "(x)\n ^ column 2: unknown character `x`"
+        );
+        assert_eq!(grammar.annotate("()"), "()\nvalid");
+    }
+}
@@ -0,0 +1,847 @@
+use std::{fmt::Display, io::Write, str::FromStr};
+
+use hashbrown::{HashMap, HashSet};
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::count::Count;
+
+/// Errors raised while parsing a cave system
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CaveError {
+    #[error("line {line}: expected `a-b`, found `{found}`")]
+    MalformedLine { line: usize, found: String },
+    #[error("line {line}: invalid cave name `{name}`")]
+    InvalidName { line: usize, name: String },
+}
+
+/// Errors raised while counting paths
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PathError {
+    #[error("no `{0}` cave")]
+    MissingCave(&'static str),
+    #[error("{0} small caves are too many to track their visits")]
+    TooManySmallCaves(usize),
+    #[error("endless paths loop through `{0}`")]
+    Unbounded(String),
+    #[error("path count overflow")]
+    Overflow,
+}
+
+/// Index of a cave in its graph
+pub type CaveId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaveKind {
+    Start,
+    End,
+    /// Lowercase name
+    Small,
+    /// Uppercase name
+    Big,
+}
+
+impl CaveKind {
+    /// Kind of the cave called `name`, `None` if it is empty or mixes cases
+    #[must_use]
+    pub fn of(name: &str) -> Option<Self> {
+        match name {
+            "start" => Some(Self::Start),
+            "end" => Some(Self::End),
+            _ if name.is_empty() || !name.chars().all(char::is_alphabetic) => None,
+            _ if name.chars().all(char::is_lowercase) => Some(Self::Small),
+            _ if name.chars().all(char::is_uppercase) => Some(Self::Big),
+            _ => None,
+        }
+    }
+
+    /// Every cave but big ones can only be visited a limited number of times
+    #[must_use]
+    pub fn is_small(self) -> bool {
+        self != Self::Big
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cave {
+    pub name: String,
+    pub kind: CaveKind,
+}
+
+/// Caves and the passages between them, caves being interned as ids
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CaveGraph {
+    caves: Vec<Cave>,
+    ids: HashMap<String, CaveId>,
+    adjacency: Vec<Vec<CaveId>>,
+    /// Passages, in the order they were added
+    edges: Vec<(CaveId, CaveId)>,
+}
+
+impl CaveGraph {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Id of the cave called `name`, adding it if needed
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is not a valid cave name, `line` being reported in the error
+    pub fn add_cave(&mut self, name: &str, line: usize) -> Result<CaveId, CaveError> {
+        if let Some(&id) = self.ids.get(name) {
+            return Ok(id);
+        }
+        let kind = CaveKind::of(name).ok_or_else(|| CaveError::InvalidName {
+            line,
+            name: name.to_string(),
+        })?;
+        let id = self.caves.len();
+        self.caves.push(Cave {
+            name: name.to_string(),
+            kind,
+        });
+        self.ids.insert(name.to_string(), id);
+        self.adjacency.push(Vec::new());
+        Ok(id)
+    }
+
+    /// Connect `a` and `b` both ways, ignoring passages already known
+    pub fn add_edge(&mut self, a: CaveId, b: CaveId) {
+        if self.adjacency[a].contains(&b) {
+            return;
+        }
+        self.adjacency[a].push(b);
+        if a != b {
+            self.adjacency[b].push(a);
+        }
+        self.edges.push((a, b));
+    }
+
+    #[must_use]
+    pub fn id(&self, name: &str) -> Option<CaveId> {
+        self.ids.get(name).copied()
+    }
+
+    #[must_use]
+    pub fn start(&self) -> Option<CaveId> {
+        self.id("start")
+    }
+
+    #[must_use]
+    pub fn end(&self) -> Option<CaveId> {
+        self.id("end")
+    }
+
+    #[must_use]
+    pub fn cave(&self, id: CaveId) -> &Cave {
+        &self.caves[id]
+    }
+
+    #[must_use]
+    pub fn name(&self, id: CaveId) -> &str {
+        &self.caves[id].name
+    }
+
+    #[must_use]
+    pub fn kind(&self, id: CaveId) -> CaveKind {
+        self.caves[id].kind
+    }
+
+    /// Caves reachable from `id` through a single passage
+    #[must_use]
+    pub fn neighbours(&self, id: CaveId) -> &[CaveId] {
+        &self.adjacency[id]
+    }
+
+    #[must_use]
+    pub fn edges(&self) -> &[(CaveId, CaveId)] {
+        &self.edges
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.caves.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.caves.is_empty()
+    }
+}
+
+impl FromStr for CaveGraph {
+    type Err = CaveError;
+
+    /// One `a-b` passage per line, blank lines being skipped
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut graph = Self::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let malformed = || CaveError::MalformedLine {
+                line: idx + 1,
+                found: line.to_string(),
+            };
+            let (a, b) = line.split_once('-').ok_or_else(malformed)?;
+            if b.contains('-') {
+                return Err(malformed());
+            }
+            let a = graph.add_cave(a.trim(), idx + 1)?;
+            let b = graph.add_cave(b.trim(), idx + 1)?;
+            graph.add_edge(a, b);
+        }
+        Ok(graph)
+    }
+}
+
+impl Display for CaveGraph {
+    /// Passages as `a-b` lines, so that the output parses back to the same graph
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for &(a, b) in &self.edges {
+            writeln!(f, "{}-{}", self.name(a), self.name(b))?;
+        }
+        Ok(())
+    }
+}
+
+/// Rules on how often caves may be visited along a path.
+///
+/// `start` is never entered again, and a path stops as soon as it enters `end`.
+pub trait VisitPolicy {
+    /// Most visits a small cave can get, used to size visit counters
+    fn max_visits(&self) -> usize;
+
+    /// Budget available at the start of a path
+    fn budget(&self) -> usize {
+        0
+    }
+
+    /// Budget left once `cave` is entered after `visits` earlier visits,
+    /// `None` if it cannot be entered. Visits to big caves are not tracked,
+    /// so `visits` is always 0 for them.
+    fn enter(&self, graph: &CaveGraph, cave: CaveId, visits: usize, budget: usize)
+        -> Option<usize>;
+}
+
+/// Small caves are visited at most once
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SmallOnce;
+
+impl VisitPolicy for SmallOnce {
+    fn max_visits(&self) -> usize {
+        1
+    }
+
+    fn enter(
+        &self,
+        graph: &CaveGraph,
+        cave: CaveId,
+        visits: usize,
+        budget: usize,
+    ) -> Option<usize> {
+        (!graph.kind(cave).is_small() || visits == 0).then_some(budget)
+    }
+}
+
+/// Small caves are visited at most once, except for a single one that may
+/// be visited twice
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OneSmallTwice;
+
+impl VisitPolicy for OneSmallTwice {
+    fn max_visits(&self) -> usize {
+        2
+    }
+
+    fn budget(&self) -> usize {
+        1
+    }
+
+    fn enter(
+        &self,
+        graph: &CaveGraph,
+        cave: CaveId,
+        visits: usize,
+        budget: usize,
+    ) -> Option<usize> {
+        match (graph.kind(cave), visits) {
+            (CaveKind::Big, _) | (_, 0) => Some(budget),
+            (CaveKind::Small, 1) if budget > 0 => Some(budget - 1),
+            _ => None,
+        }
+    }
+}
+
+/// Every small cave may be visited up to `k` times
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmallUpTo(pub usize);
+
+impl VisitPolicy for SmallUpTo {
+    fn max_visits(&self) -> usize {
+        self.0
+    }
+
+    fn enter(
+        &self,
+        graph: &CaveGraph,
+        cave: CaveId,
+        visits: usize,
+        budget: usize,
+    ) -> Option<usize> {
+        let kind = graph.kind(cave);
+        (kind == CaveKind::Big || (kind == CaveKind::End && visits == 0) || visits < self.0)
+            .then_some(budget)
+    }
+}
+
+/// Another policy, with some caves that can never be entered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forbid<P> {
+    pub policy: P,
+    pub caves: Vec<CaveId>,
+}
+
+impl<P: VisitPolicy> Forbid<P> {
+    #[must_use]
+    pub fn new(policy: P, caves: Vec<CaveId>) -> Self {
+        Self { policy, caves }
+    }
+}
+
+impl<P: VisitPolicy> VisitPolicy for Forbid<P> {
+    fn max_visits(&self) -> usize {
+        self.policy.max_visits()
+    }
+
+    fn budget(&self) -> usize {
+        self.policy.budget()
+    }
+
+    fn enter(
+        &self,
+        graph: &CaveGraph,
+        cave: CaveId,
+        visits: usize,
+        budget: usize,
+    ) -> Option<usize> {
+        if self.caves.contains(&cave) {
+            return None;
+        }
+        self.policy.enter(graph, cave, visits, budget)
+    }
+}
+
+/// Visit counts of the small caves along a path, packed in a single word
+#[derive(Debug, Clone)]
+struct Visits {
+    /// Bit offset of the counter of each small cave
+    offsets: Vec<Option<u32>>,
+    mask: u128,
+    max: usize,
+}
+
+impl Visits {
+    fn new(graph: &CaveGraph, max: usize) -> Result<Self, PathError> {
+        let bits = usize::BITS - max.leading_zeros();
+        let small = (0..graph.len())
+            .filter(|&id| graph.kind(id) == CaveKind::Small)
+            .count();
+        if bits.saturating_mul(u32::try_from(small).unwrap_or(u32::MAX)) > u128::BITS {
+            return Err(PathError::TooManySmallCaves(small));
+        }
+
+        let mut next = 0;
+        let offsets = (0..graph.len())
+            .map(|id| {
+                (graph.kind(id) == CaveKind::Small).then(|| {
+                    next += bits;
+                    next - bits
+                })
+            })
+            .collect();
+        Ok(Self {
+            offsets,
+            mask: (1 << bits) - 1,
+            max,
+        })
+    }
+
+    fn get(&self, state: u128, cave: CaveId) -> usize {
+        self.offsets[cave].map_or(0, |offset| {
+            usize::try_from((state >> offset) & self.mask).unwrap_or(usize::MAX)
+        })
+    }
+
+    /// `state` with one more visit to `cave`, `None` if its counter is full
+    fn visit(&self, state: u128, cave: CaveId) -> Option<u128> {
+        match self.offsets[cave] {
+            Some(offset) if self.get(state, cave) < self.max => Some(state + (1 << offset)),
+            Some(_) => None,
+            None => Some(state),
+        }
+    }
+}
+
+/// Current cave, visit counts of small caves and remaining budget
+type PathState = (CaveId, u128, usize);
+
+/// How a path moves from state to state under a policy
+struct Rules<'a, P> {
+    graph: &'a CaveGraph,
+    policy: &'a P,
+    visits: Visits,
+    start: CaveId,
+    end: CaveId,
+    /// States known not to reach `end`
+    dead: HashSet<PathState>,
+}
+
+impl<'a, P: VisitPolicy> Rules<'a, P> {
+    fn new(graph: &'a CaveGraph, policy: &'a P) -> Result<Self, PathError> {
+        Ok(Self {
+            graph,
+            policy,
+            visits: Visits::new(graph, policy.max_visits())?,
+            start: graph.start().ok_or(PathError::MissingCave("start"))?,
+            end: graph.end().ok_or(PathError::MissingCave("end"))?,
+            dead: HashSet::new(),
+        })
+    }
+
+    /// State reached by moving from `from` into `next`, `None` if not allowed
+    fn enter(&self, (_, state, budget): PathState, next: CaveId) -> Option<PathState> {
+        if next == self.start {
+            return None;
+        }
+        let visits = self.visits.get(state, next);
+        let budget = self.policy.enter(self.graph, next, visits, budget)?;
+        let state = self.visits.visit(state, next)?;
+        Some((next, state, budget))
+    }
+
+    fn moves(&self, from: PathState) -> impl Iterator<Item = PathState> + '_ {
+        self.graph
+            .neighbours(from.0)
+            .iter()
+            .filter_map(move |&next| self.enter(from, next))
+    }
+
+    /// Can a path go on from `from` to `end`? A loop through a state that
+    /// cannot is harmless, as it never leads to a path.
+    fn reaches_end(&mut self, from: PathState) -> bool {
+        if self.dead.contains(&from) {
+            return false;
+        }
+        let mut seen: HashSet<PathState> = [from].into_iter().collect();
+        let mut queue = vec![from];
+        while let Some(current) = queue.pop() {
+            for next in self.moves(current) {
+                if next.0 == self.end {
+                    return true;
+                }
+                if !self.dead.contains(&next) && seen.insert(next) {
+                    queue.push(next);
+                }
+            }
+        }
+        self.dead.extend(seen);
+        false
+    }
+
+    fn unbounded(&self, (cave, ..): PathState) -> PathError {
+        PathError::Unbounded(self.graph.name(cave).to_string())
+    }
+}
+
+/// Memoised path counter, keyed on the path state
+struct PathCounter<'a, P, T> {
+    rules: Rules<'a, P>,
+    /// `None` while a state is being counted, to catch endless loops
+    memo: HashMap<PathState, Option<T>>,
+}
+
+impl<P: VisitPolicy, T: Count> PathCounter<'_, P, T> {
+    fn count(&mut self, from: PathState) -> Result<T, PathError> {
+        if from.0 == self.rules.end {
+            return Ok(T::from_usize(1));
+        }
+        match self.memo.get(&from) {
+            Some(Some(count)) => return Ok(count.clone()),
+            // Back to a state being counted: endless if `end` can still be
+            // reached, no path at all otherwise
+            Some(None) if self.rules.reaches_end(from) => return Err(self.rules.unbounded(from)),
+            Some(None) => return Ok(T::zero()),
+            None => {}
+        }
+        self.memo.insert(from, None);
+
+        let mut total = T::zero();
+        let moves: Vec<_> = self.rules.moves(from).collect();
+        for next in moves {
+            let count = self.count(next)?;
+            total = total.checked_add(&count).ok_or(PathError::Overflow)?;
+        }
+
+        self.memo.insert(from, Some(total.clone()));
+        Ok(total)
+    }
+}
+
+impl CaveGraph {
+    /// Number of paths from `start` to `end` allowed by `policy`.
+    ///
+    /// Paths sharing their current cave, small cave visits and budget have the
+    /// same continuations, so each of these states is only counted once.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `start` or `end` is missing, if there are too many small caves
+    /// for their visit counts to fit in 128 bits, if paths can loop endlessly
+    /// on their way to `end` (e.g. two connected big caves), or if the count
+    /// overflows `T`
+    pub fn count_paths<T: Count>(&self, policy: &impl VisitPolicy) -> Result<T, PathError> {
+        let mut counter = PathCounter {
+            rules: Rules::new(self, policy)?,
+            memo: HashMap::new(),
+        };
+        let start = (counter.rules.start, 0, policy.budget());
+        counter.count(start)
+    }
+}
+
+/// A cave on the path being explored, along with the state reached there
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    cave: CaveId,
+    state: u128,
+    budget: usize,
+    /// Index of the next neighbour to try
+    next: usize,
+}
+
+/// Paths from `start` to `end` allowed by a policy, found lazily in depth
+/// first order. Use `take` to cap the enumeration.
+///
+/// Like `CaveGraph::count_paths`, endless loops are reported once they are
+/// reached, if `end` can still be reached from them: the iterator then yields
+/// `PathError::Unbounded` and stops.
+pub struct Paths<'a, P> {
+    rules: Rules<'a, P>,
+    required: Vec<CaveId>,
+    stack: Vec<Frame>,
+}
+
+impl<P> Paths<'_, P> {
+    /// Only keep paths going through `cave`
+    #[must_use]
+    pub fn through(mut self, cave: CaveId) -> Self {
+        self.required.push(cave);
+        self
+    }
+}
+
+impl<P: VisitPolicy> Iterator for Paths<'_, P> {
+    type Item = Result<Vec<CaveId>, PathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(frame) = self.stack.last_mut() {
+            let Some(&next) = self.rules.graph.neighbours(frame.cave).get(frame.next) else {
+                self.stack.pop();
+                continue;
+            };
+            frame.next += 1;
+            let Some(to) = self
+                .rules
+                .enter((frame.cave, frame.state, frame.budget), next)
+            else {
+                continue;
+            };
+
+            if next == self.rules.end {
+                let path: Vec<_> = self
+                    .stack
+                    .iter()
+                    .map(|frame| frame.cave)
+                    .chain(std::iter::once(next))
+                    .collect();
+                if self.required.iter().all(|cave| path.contains(cave)) {
+                    return Some(Ok(path));
+                }
+                continue;
+            }
+            // Coming back to a cave in the same state can be repeated forever,
+            // which only matters if `end` can still be reached from there
+            if self
+                .stack
+                .iter()
+                .any(|frame| (frame.cave, frame.state, frame.budget) == to)
+            {
+                if self.rules.reaches_end(to) {
+                    self.stack.clear();
+                    return Some(Err(self.rules.unbounded(to)));
+                }
+                continue;
+            }
+            let (cave, state, budget) = to;
+            self.stack.push(Frame {
+                cave,
+                state,
+                budget,
+                next: 0,
+            });
+        }
+        None
+    }
+}
+
+impl CaveGraph {
+    /// Every path from `start` to `end` allowed by `policy`
+    ///
+    /// # Errors
+    ///
+    /// Fails if `start` or `end` is missing, or if there are too many small
+    /// caves to track their visits
+    pub fn paths<'a, P: VisitPolicy>(&'a self, policy: &'a P) -> Result<Paths<'a, P>, PathError> {
+        let rules = Rules::new(self, policy)?;
+        let stack = vec![Frame {
+            cave: rules.start,
+            state: 0,
+            budget: policy.budget(),
+            next: 0,
+        }];
+        Ok(Paths {
+            rules,
+            required: Vec::new(),
+            stack,
+        })
+    }
+
+    /// `path` as comma separated cave names
+    #[must_use]
+    pub fn format_path(&self, path: &[CaveId]) -> String {
+        path.iter().map(|&id| self.name(id)).join(",")
+    }
+
+    /// Write the graph in Graphviz DOT format, drawing the caves and passages
+    /// of `highlight` in red.
+    ///
+    /// `start` and `end` are double circles, big caves are boxes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `writer` does
+    pub fn write_dot(
+        &self,
+        mut writer: impl Write,
+        highlight: Option<&[CaveId]>,
+    ) -> std::io::Result<()> {
+        const HIGHLIGHT: &str = "color=red, penwidth=2";
+        let path = highlight.unwrap_or_default();
+        let caves: HashSet<_> = path.iter().copied().collect();
+        let edges: HashSet<_> = path
+            .iter()
+            .tuple_windows()
+            .flat_map(|(&a, &b)| [(a, b), (b, a)])
+            .collect();
+
+        writeln!(writer, "graph caves {{")?;
+        for (id, cave) in self.caves.iter().enumerate() {
+            let shape = match cave.kind {
+                CaveKind::Start | CaveKind::End => "doublecircle",
+                CaveKind::Small => "circle",
+                CaveKind::Big => "box",
+            };
+            write!(writer, "    \"{}\" [shape={shape}", cave.name)?;
+            if caves.contains(&id) {
+                write!(writer, ", {HIGHLIGHT}")?;
+            }
+            writeln!(writer, "];")?;
+        }
+        for &(a, b) in &self.edges {
+            write!(writer, "    \"{}\" -- \"{}\"", self.name(a), self.name(b))?;
+            if edges.contains(&(a, b)) {
+                write!(writer, " [{HIGHLIGHT}]")?;
+            }
+            writeln!(writer, ";")?;
+        }
+        writeln!(writer, "}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::BigCount;
+
+    #[test]
+    fn test_parse() {
+        let graph: CaveGraph = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end\nb-A\n"
+            .parse()
+            .unwrap();
+        assert_eq!(graph.len(), 6);
+        assert_eq!(graph.edges().len(), 7);
+
+        let a = graph.id("A").unwrap();
+        assert_eq!(graph.kind(a), CaveKind::Big);
+        assert_eq!(graph.kind(graph.start().unwrap()), CaveKind::Start);
+        assert_eq!(graph.kind(graph.id("d").unwrap()), CaveKind::Small);
+        let mut neighbours: Vec<_> = graph
+            .neighbours(a)
+            .iter()
+            .map(|&id| graph.name(id))
+            .collect();
+        neighbours.sort_unstable();
+        assert_eq!(neighbours, vec!["b", "c", "end", "start"]);
+
+        let text = graph.to_string();
+        assert_eq!(text, "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end\n");
+        assert_eq!(text.parse::<CaveGraph>().unwrap(), graph);
+    }
+
+    #[test]
+    fn test_count_paths() {
+        let graph: CaveGraph = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end"
+            .parse()
+            .unwrap();
+        assert_eq!(graph.count_paths::<u64>(&SmallOnce), Ok(10));
+        assert_eq!(graph.count_paths::<u64>(&SmallUpTo(1)), Ok(10));
+        assert_eq!(graph.count_paths::<u64>(&OneSmallTwice), Ok(36));
+        assert_eq!(graph.count_paths::<u64>(&SmallUpTo(2)), Ok(54));
+        assert_eq!(
+            graph
+                .count_paths::<BigCount>(&OneSmallTwice)
+                .map(|count| count.to_string()),
+            Ok("36".to_string())
+        );
+
+        let b = graph.id("b").unwrap();
+        assert_eq!(
+            graph.count_paths::<u64>(&Forbid::new(SmallOnce, vec![b])),
+            Ok(2)
+        );
+        let end = graph.end().unwrap();
+        assert_eq!(
+            graph.count_paths::<u64>(&Forbid::new(OneSmallTwice, vec![end])),
+            Ok(0)
+        );
+
+        let looping: CaveGraph = "start-A\nA-B\nB-end".parse().unwrap();
+        assert_eq!(
+            looping.count_paths::<u64>(&SmallOnce),
+            Err(PathError::Unbounded("A".to_string()))
+        );
+        let no_end: CaveGraph = "start-a".parse().unwrap();
+        assert_eq!(
+            no_end.count_paths::<u64>(&SmallOnce),
+            Err(PathError::MissingCave("end"))
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        let graph: CaveGraph = "start-A\nstart-b\nA-c\nA-b\nb-d\nA-end\nb-end"
+            .parse()
+            .unwrap();
+        let mut paths = graph.paths(&SmallOnce).unwrap();
+        let first = paths.next().unwrap().unwrap();
+        assert_eq!(graph.format_path(&first), "start,A,c,A,b,A,end");
+        assert_eq!(paths.count(), 9);
+        assert_eq!(graph.paths(&OneSmallTwice).unwrap().count(), 36);
+        assert_eq!(graph.paths(&OneSmallTwice).unwrap().take(4).count(), 4);
+
+        let c = graph.id("c").unwrap();
+        let through_c: Vec<_> = graph
+            .paths(&SmallOnce)
+            .unwrap()
+            .through(c)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(through_c.len(), 5);
+        assert!(through_c.iter().all(|path| path.contains(&c)));
+
+        let mut dot = Vec::new();
+        graph.write_dot(&mut dot, Some(&through_c[0])).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("graph caves {\n    \"start\" [shape=doublecircle, color=red"));
+        assert!(dot.contains("    \"A\" -- \"c\" [color=red, penwidth=2];\n"));
+        assert!(dot.contains("    \"b\" -- \"d\";\n"));
+        assert!(dot.contains("    \"d\" [shape=circle];\n"));
+
+        // Loops are only reported once reached, as when counting
+        let looping: CaveGraph = "start-A\nA-B\nB-end".parse().unwrap();
+        let mut paths = looping.paths(&SmallOnce).unwrap();
+        assert_eq!(
+            paths.next(),
+            Some(Err(PathError::Unbounded("A".to_string())))
+        );
+        assert_eq!(paths.next(), None);
+        let unreachable: CaveGraph = "start-a\na-end\nB-C".parse().unwrap();
+        assert_eq!(unreachable.count_paths::<u64>(&SmallOnce), Ok(1));
+        assert_eq!(unreachable.paths(&SmallOnce).unwrap().count(), 1);
+        let forbidden: CaveGraph = "start-A\nA-B\nA-end".parse().unwrap();
+        let policy = Forbid::new(SmallOnce, vec![forbidden.id("B").unwrap()]);
+        assert_eq!(forbidden.count_paths::<u64>(&policy), Ok(1));
+        assert!(forbidden.paths(&policy).unwrap().all(|path| path.is_ok()));
+        assert!(forbidden.count_paths::<u64>(&SmallOnce).is_err());
+        assert!(forbidden
+            .paths(&SmallOnce)
+            .unwrap()
+            .any(|path| path.is_err()));
+
+        // A loop that cannot reach `end` any more is not endless, unless `a`
+        // can be visited twice to get out of it
+        let dead_loop: CaveGraph = "start-a\na-end\na-A\nA-B".parse().unwrap();
+        assert_eq!(dead_loop.count_paths::<u64>(&SmallOnce), Ok(1));
+        let paths: Vec<_> = dead_loop
+            .paths(&SmallOnce)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(dead_loop.format_path(&paths[0]), "start,a,end");
+        assert_eq!(
+            dead_loop.count_paths::<u64>(&OneSmallTwice),
+            Err(PathError::Unbounded("A".to_string()))
+        );
+        assert!(dead_loop
+            .paths(&OneSmallTwice)
+            .unwrap()
+            .any(|path| path.is_err()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "start-A\nAb".parse::<CaveGraph>(),
+            Err(CaveError::MalformedLine {
+                line: 2,
+                found: "Ab".to_string()
+            })
+        );
+        assert_eq!(
+            "a-b-c".parse::<CaveGraph>(),
+            Err(CaveError::MalformedLine {
+                line: 1,
+                found: "a-b-c".to_string()
+            })
+        );
+        assert_eq!(
+            "start-\n".parse::<CaveGraph>(),
+            Err(CaveError::InvalidName {
+                line: 1,
+                name: String::new()
+            })
+        );
+        assert_eq!(
+            "\nstart-Ab".parse::<CaveGraph>(),
+            Err(CaveError::InvalidName {
+                line: 2,
+                name: "Ab".to_string()
+            })
+        );
+    }
+}
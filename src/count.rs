@@ -0,0 +1,194 @@
+use std::{cmp::Ordering, fmt::Display};
+
+/// A count that may grow past any fixed width integer.
+///
+/// `u64` and `u128` report overflows, `BigCount` never overflows.
+pub trait Count: Clone {
+    fn zero() -> Self;
+    fn from_usize(value: usize) -> Self;
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+}
+
+macro_rules! impl_count {
+    ($($t:ty),*) => {
+        $(
+            impl Count for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn from_usize(value: usize) -> Self {
+                    value as $t
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+            }
+        )*
+    };
+}
+
+impl_count!(u64, u128);
+
+/// Arbitrary-precision unsigned count, stored as little-endian 64-bit limbs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigCount {
+    // No trailing zero limb, so that zero is an empty vector
+    limbs: Vec<u64>,
+}
+
+impl BigCount {
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Divide in place by a single limb, returning the remainder
+    #[allow(clippy::cast_possible_truncation)]
+    fn div_rem_limb(&mut self, divisor: u64) -> u64 {
+        let mut rem = 0_u128;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 64) | u128::from(*limb);
+            *limb = (cur / u128::from(divisor)) as u64;
+            rem = cur % u128::from(divisor);
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        rem as u64
+    }
+}
+
+impl From<u128> for BigCount {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from(value: u128) -> Self {
+        Self {
+            limbs: vec![value as u64, (value >> 64) as u64],
+        }
+        .trim()
+    }
+}
+
+impl Count for BigCount {
+    fn zero() -> Self {
+        Self::default()
+    }
+
+    fn from_usize(value: usize) -> Self {
+        Self::from(value as u128)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let (long, short) = if self.limbs.len() >= other.limbs.len() {
+            (&self.limbs, &other.limbs)
+        } else {
+            (&other.limbs, &self.limbs)
+        };
+        let mut limbs = Vec::with_capacity(long.len() + 1);
+        let mut carry = false;
+        for (idx, &limb) in long.iter().enumerate() {
+            let (sum, c1) = limb.overflowing_add(short.get(idx).copied().unwrap_or(0));
+            let (sum, c2) = sum.overflowing_add(u64::from(carry));
+            limbs.push(sum);
+            carry = c1 || c2;
+        }
+        if carry {
+            limbs.push(1);
+        }
+        Some(Self { limbs })
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        if self.is_zero() || other.is_zero() {
+            return Some(Self::zero());
+        }
+        let mut limbs = vec![0_u64; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry = 0_u128;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let cur = u128::from(a) * u128::from(b) + u128::from(limbs[i + j]) + carry;
+                limbs[i + j] = cur as u64;
+                carry = cur >> 64;
+            }
+            limbs[i + other.limbs.len()] = carry as u64;
+        }
+        Some(Self { limbs }.trim())
+    }
+}
+
+impl Ord for BigCount {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigCount {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Display for BigCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Peel off 19 decimal digits at a time, least significant first
+        let mut value = self.clone();
+        let mut chunks = Vec::new();
+        while !value.is_zero() {
+            chunks.push(value.div_rem_limb(CHUNK));
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{first}")?;
+        }
+        for chunk in chunks {
+            write!(f, "{chunk:019}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_big_count() {
+        assert_eq!(BigCount::zero().to_string(), "0");
+        assert_eq!(BigCount::from_usize(42).to_string(), "42");
+        assert!(
+            BigCount::from(u128::MAX)
+                < BigCount::from(u128::MAX)
+                    .checked_add(&BigCount::from(1))
+                    .unwrap()
+        );
+        assert_eq!(
+            BigCount::from(u128::MAX)
+                .checked_mul(&BigCount::from(u128::MAX))
+                .unwrap()
+                .to_string(),
+            "115792089237316195423570985008687907852589419931798687112530834793049593217025"
+        );
+    }
+}
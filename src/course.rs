@@ -0,0 +1,330 @@
+use std::str::FromStr;
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::submarine::{Command, CommandError};
+
+/// A single statement of a course script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Command(Command),
+    Repeat {
+        count: usize,
+        body: Vec<Statement>,
+        line: usize,
+    },
+    Call {
+        name: String,
+        line: usize,
+    },
+}
+
+/// Errors raised while parsing or expanding a course script.
+///
+/// Every variant carries the (1-based) line it refers to.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ScriptError {
+    #[error("line {line}: {source}")]
+    Command { line: usize, source: CommandError },
+    #[error("line {line}: invalid repeat count `{token}`")]
+    InvalidRepeatCount { line: usize, token: String },
+    #[error("line {line}: malformed `{keyword}` statement")]
+    Malformed { line: usize, keyword: String },
+    #[error("line {line}: unexpected `}}`")]
+    UnexpectedClose { line: usize },
+    #[error("line {line}: block is never closed")]
+    Unclosed { line: usize },
+    #[error("line {line}: routines can only be defined at top level")]
+    NestedDef { line: usize },
+    #[error("line {line}: routine `{name}` is already defined")]
+    DuplicateRoutine { line: usize, name: String },
+    #[error("line {line}: unknown routine `{name}`")]
+    UnknownRoutine { line: usize, name: String },
+    #[error("line {line}: routine `{name}` calls itself")]
+    RecursiveCall { line: usize, name: String },
+    #[error("line {line}: script expands to more than {limit} commands")]
+    TooLong { line: usize, limit: usize },
+}
+
+/// A parsed course script: a main body plus named routines.
+///
+/// ```text
+/// # go down in steps
+/// def step {
+///     forward 5
+///     down 2
+/// }
+/// repeat 3 {
+///     call step
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Script {
+    pub routines: HashMap<String, Vec<Statement>>,
+    pub body: Vec<Statement>,
+}
+
+enum Block {
+    Root,
+    Repeat { count: usize },
+    Def { name: String, line: usize },
+}
+
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    /// Parse a course script
+    ///
+    /// # Errors
+    ///
+    /// Fails on invalid commands, malformed blocks or duplicate routines
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut script = Script::default();
+        // Currently open blocks, with the line they were opened on
+        let mut stack: Vec<(Block, usize, Vec<Statement>)> = vec![(Block::Root, 0, Vec::new())];
+
+        for (idx, raw) in s.lines().enumerate() {
+            let line = idx + 1;
+            let content = raw.split('#').next().unwrap_or("").trim();
+            let tokens: Vec<_> = content.split_whitespace().collect();
+
+            match tokens.as_slice() {
+                [] => {}
+                ["}"] => {
+                    let (block, opened, body) =
+                        stack.pop().ok_or(ScriptError::UnexpectedClose { line })?;
+                    match block {
+                        Block::Root => return Err(ScriptError::UnexpectedClose { line }),
+                        Block::Repeat { count } => {
+                            let line = opened;
+                            push_statement(&mut stack, Statement::Repeat { count, body, line });
+                        }
+                        Block::Def { name, line } => {
+                            if script.routines.contains_key(&name) {
+                                return Err(ScriptError::DuplicateRoutine { line, name });
+                            }
+                            script.routines.insert(name, body);
+                        }
+                    }
+                }
+                ["repeat", count, "{"] => {
+                    let count = count.parse().map_err(|_| ScriptError::InvalidRepeatCount {
+                        line,
+                        token: (*count).to_string(),
+                    })?;
+                    stack.push((Block::Repeat { count }, line, Vec::new()));
+                }
+                ["def", name, "{"] => {
+                    if stack.len() != 1 {
+                        return Err(ScriptError::NestedDef { line });
+                    }
+                    let name = (*name).to_string();
+                    stack.push((Block::Def { name, line }, line, Vec::new()));
+                }
+                ["call", name] => {
+                    let name = (*name).to_string();
+                    push_statement(&mut stack, Statement::Call { name, line });
+                }
+                [keyword @ ("repeat" | "def" | "call"), ..] => {
+                    return Err(ScriptError::Malformed {
+                        line,
+                        keyword: (*keyword).to_string(),
+                    });
+                }
+                _ => {
+                    let command = content
+                        .parse::<Command>()
+                        .map_err(|source| ScriptError::Command { line, source })?;
+                    push_statement(&mut stack, Statement::Command(command));
+                }
+            }
+        }
+
+        let (block, line, body) = stack
+            .pop()
+            .ok_or(ScriptError::UnexpectedClose { line: 0 })?;
+        if !matches!(block, Block::Root) {
+            return Err(ScriptError::Unclosed { line });
+        }
+        script.body = body;
+        Ok(script)
+    }
+}
+
+fn push_statement(stack: &mut [(Block, usize, Vec<Statement>)], statement: Statement) {
+    if let Some((_, _, body)) = stack.last_mut() {
+        body.push(statement);
+    }
+}
+
+impl Script {
+    /// Most commands a script can expand to
+    pub const MAX_COMMANDS: usize = 1 << 20;
+
+    /// Expand repeats and routine calls into a plain command stream
+    ///
+    /// # Errors
+    ///
+    /// Fails if a called routine does not exist or calls itself, or if the
+    /// script expands to more than `MAX_COMMANDS` commands
+    pub fn expand(&self) -> Result<Vec<Command>, ScriptError> {
+        let mut commands = Vec::new();
+        let mut call_stack = Vec::new();
+        self.expand_into(&self.body, &mut call_stack, &mut commands)?;
+        Ok(commands)
+    }
+
+    fn expand_into<'a>(
+        &'a self,
+        statements: &'a [Statement],
+        call_stack: &mut Vec<&'a str>,
+        commands: &mut Vec<Command>,
+    ) -> Result<(), ScriptError> {
+        for statement in statements {
+            match statement {
+                Statement::Command(command) => commands.push(*command),
+                Statement::Repeat { count, body, line } => {
+                    for _ in 0..*count {
+                        self.expand_into(body, call_stack, commands)?;
+                        check_length(commands, *line)?;
+                    }
+                }
+                Statement::Call { name, line } => {
+                    if call_stack.contains(&name.as_str()) {
+                        return Err(ScriptError::RecursiveCall {
+                            line: *line,
+                            name: name.clone(),
+                        });
+                    }
+                    let body =
+                        self.routines
+                            .get(name)
+                            .ok_or_else(|| ScriptError::UnknownRoutine {
+                                line: *line,
+                                name: name.clone(),
+                            })?;
+                    call_stack.push(name);
+                    self.expand_into(body, call_stack, commands)?;
+                    call_stack.pop();
+                    check_length(commands, *line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Only repeats and calls multiply commands, so checking the length after
+/// each of them keeps the expansion bounded
+fn check_length(commands: &[Command], line: usize) -> Result<(), ScriptError> {
+    if commands.len() > Script::MAX_COMMANDS {
+        return Err(ScriptError::TooLong {
+            line,
+            limit: Script::MAX_COMMANDS,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::submarine::Submarine;
+    use std::fmt::Write as _;
+
+    #[test]
+    fn test_expand_repeat_and_call() {
+        let script = r"# descend in steps
+def step {
+    forward 5 # move
+    down 2
+}
+repeat 3 {
+    call step
+}
+up 1";
+        let commands = script.parse::<Script>().unwrap().expand().unwrap();
+        let expanded: Vec<_> = commands.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            expanded,
+            vec![
+                "forward 5",
+                "down 2",
+                "forward 5",
+                "down 2",
+                "forward 5",
+                "down 2",
+                "up 1"
+            ]
+        );
+
+        let mut submarine = Submarine::new();
+        commands.iter().for_each(|c| submarine.apply(c));
+        assert_eq!((submarine.horizontal, submarine.depth), (15, 5));
+    }
+
+    #[test]
+    fn test_script_errors() {
+        assert_eq!(
+            "forward 1\nrepeat x {\n}".parse::<Script>(),
+            Err(ScriptError::InvalidRepeatCount {
+                line: 2,
+                token: "x".to_string()
+            })
+        );
+        assert_eq!(
+            "repeat 2 {\nforward 1".parse::<Script>(),
+            Err(ScriptError::Unclosed { line: 1 })
+        );
+        assert_eq!(
+            "forward 1\n}".parse::<Script>(),
+            Err(ScriptError::UnexpectedClose { line: 2 })
+        );
+        assert_eq!(
+            "\n\nforward".parse::<Script>(),
+            Err(ScriptError::Command {
+                line: 3,
+                source: CommandError::MissingValue(crate::submarine::Direction::Forward)
+            })
+        );
+        assert_eq!(
+            "def a {\ncall a\n}\ncall a"
+                .parse::<Script>()
+                .unwrap()
+                .expand(),
+            Err(ScriptError::RecursiveCall {
+                line: 2,
+                name: "a".to_string()
+            })
+        );
+        assert_eq!(
+            "call nowhere".parse::<Script>().unwrap().expand(),
+            Err(ScriptError::UnknownRoutine {
+                line: 1,
+                name: "nowhere".to_string()
+            })
+        );
+        assert_eq!(
+            "repeat 1000000000 {\nrepeat 1000000000 {\nforward 1\n}\n}"
+                .parse::<Script>()
+                .unwrap()
+                .expand(),
+            Err(ScriptError::TooLong {
+                line: 2,
+                limit: Script::MAX_COMMANDS
+            })
+        );
+        // Each routine calls the previous one twice, doubling the expansion
+        let mut calls = "def a {\nforward 1\n}\n".to_string();
+        for name in 'b'..='z' {
+            let called = char::from_u32(u32::from(name) - 1).unwrap();
+            let _ = write!(calls, "def {name} {{\ncall {called}\ncall {called}\n}}\n");
+        }
+        calls += "call z";
+        assert!(matches!(
+            calls.parse::<Script>().unwrap().expand(),
+            Err(ScriptError::TooLong { .. })
+        ));
+    }
+}
@@ -0,0 +1,525 @@
+use std::{fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+/// Best alignment position and the fuel it costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: isize,
+    pub cost: isize,
+}
+
+/// Errors raised while parsing a fleet, each carrying the offending token
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CrabError {
+    #[error("`{0}`: expected `position:weight:model`")]
+    Malformed(String),
+    #[error("`{0}`: invalid position")]
+    InvalidPosition(String),
+    #[error("`{0}`: invalid weight, expected a non-negative integer")]
+    InvalidWeight(String),
+    #[error("`{0}`: unknown cost model")]
+    UnknownModel(String),
+}
+
+/// How much fuel a crab burns to move a given distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostModel {
+    /// One unit per step
+    Linear,
+    /// One more unit for every step: 1, 3, 6, 10...
+    Triangular,
+    /// Distance squared
+    Quadratic,
+    /// One unit per step, never more than `cap` units
+    Capped(isize),
+}
+
+impl CostModel {
+    #[must_use]
+    pub fn cost(self, distance: isize) -> isize {
+        let distance = distance.abs();
+        match self {
+            Self::Linear => distance,
+            Self::Triangular => distance * (distance + 1) / 2,
+            Self::Quadratic => distance * distance,
+            Self::Capped(cap) => distance.min(cap),
+        }
+    }
+
+    /// Is the cost convex in the distance? Capped costs are not.
+    #[must_use]
+    pub fn is_convex(self) -> bool {
+        !matches!(self, Self::Capped(_))
+    }
+
+    /// Slope of the cost of a crab at `position` far to its left, and the
+    /// positions where it changes along with the change, for costs that are
+    /// piecewise linear. `None` for triangular and quadratic costs.
+    fn slope_changes(self, position: isize) -> Option<(isize, Vec<(isize, isize)>)> {
+        match self {
+            Self::Linear => Some((-1, vec![(position, 2)])),
+            Self::Capped(cap) => Some((
+                0,
+                vec![(position - cap, -1), (position, 2), (position + cap, -1)],
+            )),
+            Self::Triangular | Self::Quadratic => None,
+        }
+    }
+}
+
+impl FromStr for CostModel {
+    type Err = CrabError;
+
+    /// `linear`, `triangular`, `quadratic` or `capped(<cap>)`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "triangular" => Ok(Self::Triangular),
+            "quadratic" => Ok(Self::Quadratic),
+            _ => s
+                .strip_prefix("capped(")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|cap| cap.parse().ok())
+                .filter(|&cap| cap >= 0)
+                .map(Self::Capped)
+                .ok_or_else(|| CrabError::UnknownModel(s.to_string())),
+        }
+    }
+}
+
+impl Display for CostModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Triangular => write!(f, "triangular"),
+            Self::Quadratic => write!(f, "quadratic"),
+            Self::Capped(cap) => write!(f, "capped({cap})"),
+        }
+    }
+}
+
+/// A crab with its own fuel efficiency: every unit of cost is paid `weight` times
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crab {
+    pub position: isize,
+    pub weight: isize,
+    pub model: CostModel,
+}
+
+impl Crab {
+    /// Fuel this crab burns to reach `target`
+    #[must_use]
+    pub fn fuel(&self, target: isize) -> isize {
+        self.weight * self.model.cost(target - self.position)
+    }
+}
+
+impl FromStr for Crab {
+    type Err = CrabError;
+
+    /// `position:weight:model`, e.g. `16:2:triangular` or `-3:1:capped(5)`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(':');
+        let (Some(position), Some(weight), Some(model), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(CrabError::Malformed(s.to_string()));
+        };
+
+        Ok(Self {
+            position: position
+                .parse()
+                .map_err(|_| CrabError::InvalidPosition(s.to_string()))?,
+            weight: weight
+                .parse()
+                .ok()
+                .filter(|&weight| weight >= 0)
+                .ok_or_else(|| CrabError::InvalidWeight(s.to_string()))?,
+            model: model.parse()?,
+        })
+    }
+}
+
+impl Display for Crab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.position, self.weight, self.model)
+    }
+}
+
+/// Parse crabs separated by commas or whitespace
+///
+/// # Errors
+///
+/// Fails on the first crab that cannot be parsed
+pub fn parse_fleet(input: &str) -> Result<Vec<Crab>, CrabError> {
+    input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Fuel needed to bring every crab to `target`
+#[must_use]
+pub fn total_cost(positions: &[isize], target: isize, cost: impl Fn(isize) -> isize) -> isize {
+    positions.iter().map(|&pos| cost(target - pos)).sum()
+}
+
+/// Cheapest alignment under `model`, `None` without any crab.
+///
+/// * linear cost is minimal on the median
+/// * triangular and quadratic costs are minimal within half a step of the mean
+/// * capped costs are piecewise linear, so only their breakpoints are tried
+///
+/// Ties are broken towards the lowest position.
+#[must_use]
+pub fn align(positions: &[isize], model: CostModel) -> Option<Alignment> {
+    let cost = |distance| model.cost(distance);
+    match model {
+        CostModel::Linear => {
+            let mut sorted = positions.to_vec();
+            let mid = sorted.len().checked_sub(1)? / 2;
+            let (_, &mut position, _) = sorted.select_nth_unstable(mid);
+            Some(Alignment {
+                position,
+                cost: total_cost(positions, position, cost),
+            })
+        }
+        CostModel::Triangular | CostModel::Quadratic => {
+            let len = isize::try_from(positions.len())
+                .ok()
+                .filter(|&len| len > 0)?;
+            let mean = positions.iter().sum::<isize>().div_euclid(len);
+            (mean - 1..=mean + 2)
+                .map(|position| Alignment {
+                    position,
+                    cost: total_cost(positions, position, cost),
+                })
+                .min_by_key(|alignment| (alignment.cost, alignment.position))
+        }
+        CostModel::Capped(_) => {
+            let (&min, &max) = (positions.iter().min()?, positions.iter().max()?);
+            let changes = positions
+                .iter()
+                .filter_map(|&position| model.slope_changes(position))
+                .flat_map(|(_, changes)| changes)
+                .collect();
+            Some(sweep(
+                min,
+                max,
+                total_cost(positions, min, cost),
+                0,
+                changes,
+            ))
+        }
+    }
+}
+
+/// Cheapest alignment for any `cost` of the distance that is convex and grows
+/// with the distance, `None` without any crab.
+///
+/// This is a ternary search on integers: the total cost is convex in the
+/// target, so bisecting on the sign of `f(x + 1) - f(x)` finds its lowest
+/// minimum in O(log(range)) evaluations.
+#[must_use]
+pub fn align_convex(positions: &[isize], cost: impl Fn(isize) -> isize) -> Option<Alignment> {
+    let (&min, &max) = (positions.iter().min()?, positions.iter().max()?);
+    Some(bisect(min, max, |target| {
+        total_cost(positions, target, &cost)
+    }))
+}
+
+/// Cheapest alignment of a fleet, `None` without any crab.
+///
+/// * when every model is convex, the total is convex and bisected
+/// * when every model is linear or capped, the total is piecewise linear and
+///   only its breakpoints are tried
+/// * otherwise, the total is convex between the breakpoints of capped crabs,
+///   and every piece is bisected
+#[must_use]
+pub fn align_fleet(crabs: &[Crab]) -> Option<Alignment> {
+    let min = crabs.iter().map(|crab| crab.position).min()?;
+    let max = crabs.iter().map(|crab| crab.position).max()?;
+    let total = |target| crabs.iter().map(|crab| crab.fuel(target)).sum();
+
+    if crabs.iter().all(|crab| crab.model.is_convex()) {
+        return Some(bisect(min, max, total));
+    }
+    let pieces: Option<Vec<_>> = crabs
+        .iter()
+        .map(|crab| {
+            let (left, changes) = crab.model.slope_changes(crab.position)?;
+            Some((crab.weight * left, changes, crab.weight))
+        })
+        .collect();
+    if let Some(pieces) = pieces {
+        let left = pieces.iter().map(|(left, _, _)| left).sum();
+        let changes = pieces
+            .into_iter()
+            .flat_map(|(_, changes, weight)| {
+                changes
+                    .into_iter()
+                    .map(move |(at, delta)| (at, delta * weight))
+            })
+            .collect();
+        return Some(sweep(min, max, total(min), left, changes));
+    }
+
+    let mut breakpoints: Vec<_> = crabs
+        .iter()
+        .filter_map(|crab| match crab.model {
+            CostModel::Capped(cap) => Some([crab.position - cap, crab.position + cap]),
+            _ => None,
+        })
+        .flatten()
+        .filter(|&at| min < at && at < max)
+        .chain([min, max])
+        .collect();
+    breakpoints.sort_unstable();
+    breakpoints.dedup();
+    breakpoints
+        .windows(2)
+        .map(|piece| bisect(piece[0], piece[1], total))
+        .chain((min == max).then(|| bisect(min, max, total)))
+        .min_by_key(|alignment| (alignment.cost, alignment.position))
+}
+
+/// Fuel burnt by each crab to reach `target`, in fleet order
+#[must_use]
+pub fn fuel_report(crabs: &[Crab], target: isize) -> Vec<isize> {
+    crabs.iter().map(|crab| crab.fuel(target)).collect()
+}
+
+/// Lowest minimum over `min..=max` of a piecewise linear total, costing
+/// `at_min` on `min`. Its slope is `left` far to the left of every crab,
+/// and changes by `delta` right after each `(at, delta)`.
+///
+/// A minimum is always on a breakpoint or an end of the range, so the total
+/// is walked from breakpoint to breakpoint in O(n log(n)).
+fn sweep(
+    min: isize,
+    max: isize,
+    at_min: isize,
+    left: isize,
+    mut changes: Vec<(isize, isize)>,
+) -> Alignment {
+    changes.sort_unstable();
+    let mut slope: isize = left
+        + changes
+            .iter()
+            .filter(|&&(at, _)| at <= min)
+            .map(|&(_, delta)| delta)
+            .sum::<isize>();
+    let mut best = Alignment {
+        position: min,
+        cost: at_min,
+    };
+    let mut current = best;
+    for (at, group) in &changes
+        .iter()
+        .filter(|&&(at, _)| min < at && at < max)
+        .chunk_by(|&&(at, _)| at)
+    {
+        current = Alignment {
+            position: at,
+            cost: current.cost + slope * (at - current.position),
+        };
+        if current.cost < best.cost {
+            best = current;
+        }
+        slope += group.map(|&(_, delta)| delta).sum::<isize>();
+    }
+    let last = current.cost + slope * (max - current.position);
+    if last < best.cost {
+        best = Alignment {
+            position: max,
+            cost: last,
+        };
+    }
+    best
+}
+
+/// Lowest minimum of a convex `total` over `min..=max`
+fn bisect(min: isize, max: isize, total: impl Fn(isize) -> isize) -> Alignment {
+    // First target from which the total cost stops decreasing
+    let (mut low, mut high) = (min, max);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if total(mid + 1) >= total(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Alignment {
+        position: low,
+        cost: total(low),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: [isize; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+    fn brute_force(positions: &[isize], cost: impl Fn(isize) -> isize) -> Alignment {
+        let (&min, &max) = (
+            positions.iter().min().unwrap(),
+            positions.iter().max().unwrap(),
+        );
+        (min..=max)
+            .map(|position| Alignment {
+                position,
+                cost: total_cost(positions, position, &cost),
+            })
+            .min_by_key(|alignment| (alignment.cost, alignment.position))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_closed_forms() {
+        assert_eq!(
+            align(&SAMPLE, CostModel::Linear),
+            Some(Alignment {
+                position: 2,
+                cost: 37
+            })
+        );
+        assert_eq!(
+            align(&SAMPLE, CostModel::Triangular),
+            Some(Alignment {
+                position: 5,
+                cost: 168
+            })
+        );
+        assert_eq!(align(&[], CostModel::Linear), None);
+        assert_eq!(align(&[], CostModel::Triangular), None);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let inputs: [&[isize]; 4] = [&SAMPLE, &[-5, 3], &[7], &[0, 0, 10, 10, 10, -3, 25]];
+        for positions in inputs {
+            for model in [
+                CostModel::Linear,
+                CostModel::Triangular,
+                CostModel::Quadratic,
+            ] {
+                let expected = brute_force(positions, |d| model.cost(d));
+                assert_eq!(align(positions, model), Some(expected), "{positions:?}");
+                assert_eq!(
+                    align_convex(positions, |d| model.cost(d)),
+                    Some(expected),
+                    "{positions:?}"
+                );
+            }
+            let capped = CostModel::Capped(4);
+            assert_eq!(
+                align(positions, capped),
+                Some(brute_force(positions, |d| capped.cost(d)))
+            );
+        }
+        assert_eq!(align_convex(&[], |d| d), None);
+    }
+
+    #[test]
+    fn test_fleet() {
+        let fleet = parse_fleet("0:1:linear, 10:3:linear\n4:2:capped(3) 7:1:quadratic").unwrap();
+        assert_eq!(
+            fleet[2],
+            Crab {
+                position: 4,
+                weight: 2,
+                model: CostModel::Capped(3)
+            }
+        );
+        assert_eq!(fleet[2].to_string(), "4:2:capped(3)");
+
+        let alignment = align_fleet(&fleet).unwrap();
+        let brute = (0..=10)
+            .map(|position| Alignment {
+                position,
+                cost: fuel_report(&fleet, position).iter().sum(),
+            })
+            .min_by_key(|alignment| (alignment.cost, alignment.position))
+            .unwrap();
+        assert_eq!(alignment, brute);
+        assert_eq!(
+            alignment,
+            Alignment {
+                position: 8,
+                cost: 8 + 6 + 6 + 1
+            }
+        );
+        assert_eq!(fuel_report(&fleet, 9), vec![9, 3, 6, 4]);
+
+        let convex: Vec<_> = fleet
+            .iter()
+            .map(|&crab| Crab {
+                model: CostModel::Triangular,
+                ..crab
+            })
+            .collect();
+        let weighted: Vec<_> = convex
+            .iter()
+            .flat_map(|crab| {
+                std::iter::repeat_n(crab.position, usize::try_from(crab.weight).unwrap())
+            })
+            .collect();
+        assert_eq!(
+            align_fleet(&convex),
+            align(&weighted, CostModel::Triangular)
+        );
+    }
+
+    #[test]
+    fn test_piecewise_linear_fleet() {
+        for input in [
+            "0:2:capped(3) 3:1:linear 9:4:capped(2) 11:1:capped(0) 20:3:capped(6)",
+            "0:1:linear 4:1:capped(2) 5:1:capped(2) 6:1:capped(2) 20:1:linear",
+            "-4:3:linear 2:1:capped(9) 7:2:linear 8:5:capped(1)",
+        ] {
+            let fleet = parse_fleet(input).unwrap();
+            let min = fleet.iter().map(|crab| crab.position).min().unwrap();
+            let max = fleet.iter().map(|crab| crab.position).max().unwrap();
+            let brute = (min..=max)
+                .map(|position| Alignment {
+                    position,
+                    cost: fuel_report(&fleet, position).iter().sum(),
+                })
+                .min_by_key(|alignment| (alignment.cost, alignment.position))
+                .unwrap();
+            assert_eq!(align_fleet(&fleet), Some(brute), "{input}");
+        }
+        assert_eq!(
+            align_fleet(&parse_fleet("0:2:capped(3)").unwrap()),
+            Some(Alignment {
+                position: 0,
+                cost: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_fleet_errors() {
+        assert_eq!(
+            parse_fleet("1:1"),
+            Err(CrabError::Malformed("1:1".to_string()))
+        );
+        assert_eq!(
+            parse_fleet("x:1:linear"),
+            Err(CrabError::InvalidPosition("x:1:linear".to_string()))
+        );
+        assert_eq!(
+            parse_fleet("1:-2:linear"),
+            Err(CrabError::InvalidWeight("1:-2:linear".to_string()))
+        );
+        assert_eq!(
+            parse_fleet("1:1:capped(x)"),
+            Err(CrabError::UnknownModel("capped(x)".to_string()))
+        );
+    }
+}
@@ -15,16 +15,19 @@ pub fn process(data: &[usize], step: NonZeroUsize) -> usize {
         .count()
 }
 
+const PART_1_STEP: NonZeroUsize = NonZeroUsize::new(2).unwrap();
+const PART_2_STEP: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
 /// Process data for a given step
 #[must_use]
 pub fn part_1(data: &[usize]) -> usize {
-    process(data, unsafe { NonZeroUsize::new_unchecked(2) })
+    process(data, PART_1_STEP)
 }
 
 /// Process data for a given step
 #[must_use]
 pub fn part_2(data: &[usize]) -> usize {
-    process(data, unsafe { NonZeroUsize::new_unchecked(4) })
+    process(data, PART_2_STEP)
 }
 
 /// Process solutions for day 1
@@ -1,106 +1,51 @@
 use std::time::Instant;
 
-use adventofcode_tooling::{read_lines, AocError};
+use adventofcode_2021::{
+    brackets::{BracketGrammar, GrammarError, LineStatus, ScoreError},
+    options::Options,
+};
+use adventofcode_tooling::read_lines;
 
-#[must_use]
-pub fn part_1(values: &[String]) -> usize {
-    values
-        .iter()
-        .filter_map(|s| {
-            if let ExpressionStatus::Corrupted(c) = process(s) {
-                Some(c)
-            } else {
-                None
-            }
-        })
-        .map(|c| match c {
-            ')' => 3,
-            ']' => 57,
-            '}' => 1197,
-            '>' => 25137,
-            _ => 0,
-        })
-        .sum()
-}
-
-#[must_use]
-pub fn part_2(values: &[String]) -> Option<u128> {
-    let mut tab = values
-        .iter()
-        .filter_map(|s| {
-            if let ExpressionStatus::Incomplete(v) = process(s) {
-                Some(v)
-            } else {
-                None
-            }
-        })
-        .map(|v| {
-            v.iter().rev().fold(0, |mut acc: u128, &c| {
-                acc *= 5;
-                acc += match c {
-                    '(' => 1,
-                    '[' => 2,
-                    '{' => 3,
-                    '<' => 4,
-                    _ => 0,
-                };
-                acc
-            })
-        })
-        .collect::<Vec<_>>();
-    tab.sort_unstable();
-
-    let len = tab.len();
-    if len & 1 != 1 {
-        println!("Number of incomplete lines is assumed to be even.");
-        println!("Result is probably wrong");
+/// Total score of corrupted lines
+///
+/// # Errors
+///
+/// Fails on a line holding an unknown character
+pub fn part_1(values: &[String]) -> Result<u64, GrammarError> {
+    let grammar = BracketGrammar::navigation();
+    let mut score = 0;
+    for line in values {
+        if let LineStatus::Corrupted(corruption) = grammar.check(line)? {
+            score += grammar.corrupted_score(corruption.found).unwrap_or(0);
+        }
     }
-    let middle = tab.len() / 2;
-
-    tab.get(middle).copied()
-}
-
-pub enum ExpressionStatus {
-    Valid,
-    Incomplete(Vec<char>),
-    Corrupted(char),
+    Ok(score)
 }
 
-#[must_use]
-pub fn process(expr: &str) -> ExpressionStatus {
-    let mut stack = Vec::<char>::new();
-
-    for c in expr.chars() {
-        match c {
-            '}' => {
-                if stack.pop() != Some('{') {
-                    return ExpressionStatus::Corrupted(c);
-                }
-            }
-            ']' => {
-                if stack.pop() != Some('[') {
-                    return ExpressionStatus::Corrupted(c);
-                }
-            }
-            ')' => {
-                if stack.pop() != Some('(') {
-                    return ExpressionStatus::Corrupted(c);
-                }
-            }
-            '>' => {
-                if stack.pop() != Some('<') {
-                    return ExpressionStatus::Corrupted(c);
-                }
-            }
-            _ => {
-                stack.push(c);
-            }
+/// Middle completion score of incomplete lines
+///
+/// # Errors
+///
+/// Fails on a line holding an unknown character, if a completion score
+/// overflows, or if the number of incomplete lines is even
+pub fn part_2(values: &[String]) -> Result<u128, ScoreError> {
+    let grammar = BracketGrammar::navigation();
+    let mut tab = Vec::new();
+    for line in values {
+        if let LineStatus::Incomplete(openers) = grammar.check(line)? {
+            tab.push(
+                grammar
+                    .completion_score(&openers)
+                    .ok_or(ScoreError::Overflow)?,
+            );
         }
     }
-    if !stack.is_empty() {
-        return ExpressionStatus::Incomplete(stack);
+    tab.sort_unstable();
+
+    if tab.len() % 2 == 0 {
+        return Err(ScoreError::EvenIncompleteCount(tab.len()));
     }
-    ExpressionStatus::Valid
+    Ok(tab[tab.len() / 2])
 }
 
 /// Process solutions for day 10
@@ -108,16 +53,27 @@ pub fn process(expr: &str) -> ExpressionStatus {
 /// # Errors
 ///
 /// May fail if input data cannot be read
-pub fn main() -> Result<(), AocError> {
+///
+/// Options:
+/// * `--annotate`: show where each line goes wrong, and how to repair it
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now = Instant::now();
     let input_data: Vec<String> = read_lines("day_2021_10.data")?
         .map(Result::unwrap)
         .collect();
 
-    println!("Part 1: {:?}", part_1(&input_data));
-    println!("Part 2: {:?}", part_2(&input_data));
+    println!("Part 1: {:?}", part_1(&input_data)?);
+    println!("Part 2: {:?}", part_2(&input_data)?);
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
+
+    if Options::from_env().flag("--annotate") {
+        let grammar = BracketGrammar::navigation();
+        for line in &input_data {
+            println!("{}", grammar.annotate(line));
+            println!("repair: {:?}\n", grammar.repair(line)?);
+        }
+    }
     Ok(())
 }
 
@@ -144,7 +100,7 @@ mod tests {
             .map(std::string::ToString::to_string)
             .collect::<Vec<String>>();
 
-        assert_eq!(part_1(&input_data), 26397);
+        assert_eq!(part_1(&input_data).unwrap(), 26397);
     }
 
     #[test]
@@ -165,6 +121,10 @@ mod tests {
             .map(std::string::ToString::to_string)
             .collect::<Vec<String>>();
 
-        assert_eq!(part_2(&input_data), Some(288957));
+        assert_eq!(part_2(&input_data).unwrap(), 288957);
+        assert_eq!(
+            part_2(&input_data[..2]),
+            Err(ScoreError::EvenIncompleteCount(2))
+        );
     }
 }
@@ -1,95 +1,83 @@
 use std::time::Instant;
 
-use adventofcode_2021::Matrix2D;
-use adventofcode_tooling::{read_lines, AocError};
+use adventofcode_2021::{
+    octopus::{OctopusGrid, CLEAR_SCREEN},
+    options::Options,
+    Matrix2D,
+};
+use adventofcode_tooling::read_lines;
 
 #[must_use]
 pub fn part_1(matrix: &Matrix2D<u8>) -> usize {
-    let mut part_matrix = matrix.clone();
-    (0..100).map(|_| turn(&mut part_matrix)).sum()
+    let mut octopuses = OctopusGrid::new(matrix.clone());
+    (0..100).map(|_| octopuses.step().count()).sum()
 }
 
 #[must_use]
 pub fn part_2(matrix: &Matrix2D<u8>) -> usize {
-    let mut part_matrix = matrix.clone();
-    let mut turns = 0;
-    loop {
+    let mut octopuses = OctopusGrid::new(matrix.clone());
+    let mut turns = 1;
+    while !octopuses.step().all_flashed {
         turns += 1;
-        if turn(&mut part_matrix) == 100 {
-            break;
-        }
     }
     turns
 }
 
+/// Run one step on `matrix`, returning the number of flashes
 #[must_use]
 pub fn turn(matrix: &mut Matrix2D<u8>) -> usize {
-    let width = matrix.width;
-    // First, we increase all octopus by 1
-    for v in &mut matrix.values {
-        *v += 1;
-    }
-
-    // We loop until no more octopus need to flash
-    loop {
-        let need_to_flash: Vec<_> = matrix
-            .values
-            .iter()
-            .enumerate()
-            .filter_map(|(u, v)| if *v > 9 { Some(u) } else { None })
-            .collect();
-
-        if need_to_flash.is_empty() {
-            break;
-        }
-
-        for point in need_to_flash {
-            let (x, y) = (point % width, point / width);
-            // Reset the octopus that has flashed
-            if let Some(v) = matrix.values.get_mut(point) {
-                *v = 0;
-            }
-
-            // x is not left, not right:
-            let neighbours = matrix.get_neighbours_coord(x, y, true);
-
-            for position in neighbours {
-                if let Some(val) = matrix.values.get_mut(position.1 * width + position.0) {
-                    *val = if *val == 0 { 0 } else { *val + 1 };
-                }
-            }
-        }
-    }
-    bytecount::count(&matrix.values, 0)
+    let mut octopuses = OctopusGrid::new(std::mem::replace(matrix, Matrix2D::new(0, 0)));
+    let flashes = octopuses.step().count();
+    *matrix = octopuses.into_energy();
+    flashes
 }
 
 /// Process solutions for day 11
 ///
 /// # Errors
 ///
-/// May fail if input data cannot be read
-pub fn main() -> Result<(), AocError> {
+/// May fail if input data cannot be read, if `--from` is after `--to`, or if
+/// frames cannot be written
+///
+/// Options:
+/// * `--animate`: play the grid back in the terminal
+/// * `--pgm <dir>`: write each step as a PGM image in `dir`, created if missing
+/// * `--from <step>`, `--to <step>`: steps to show, 0 to 100 by default
+/// * `--delay <ms>`: time between two frames when animating, 100 ms by default
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now = Instant::now();
     let input_data: Vec<_> = read_lines("day_2021_11.data")?
         .map(Result::unwrap)
         .collect();
-    let input_data: Vec<u8> = input_data
-        .iter()
-        .flat_map(|s| s.chars())
-        .map(|s| s.to_digit(10).unwrap())
-        .map(|d| u8::try_from(d).unwrap())
-        .collect();
-
-    let matrix = {
-        let mut matrix = Matrix2D::new(10, 10);
-        matrix.values = input_data;
-        matrix
-    };
+    let matrix = Matrix2D::from_digits(&input_data).ok_or("invalid octopus grid")?;
 
     println!("Part 1: {:?}", part_1(&matrix));
     println!("Part 2: {}", part_2(&matrix));
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
+
+    let options = Options::from_env();
+    let pgm = options.value("--pgm")?;
+    let animate = options.flag("--animate");
+    if animate || pgm.is_some() {
+        let from = options.value("--from")?.map_or(Ok(0), str::parse)?;
+        let to = options.value("--to")?.map_or(Ok(100), str::parse)?;
+        if from > to {
+            return Err(format!("empty step range: --from {from} is after --to {to}").into());
+        }
+        let recording = OctopusGrid::new(matrix).record(from..=to);
+        if let Some(dir) = pgm {
+            let paths = recording.write_pgm(std::path::Path::new(dir))?;
+            println!("{} frames written to {dir}", paths.len());
+        }
+        if animate {
+            let delay = options.value("--delay")?.map_or(Ok(100), str::parse)?;
+            for frame in &recording.frames {
+                print!("{CLEAR_SCREEN}{}", frame.to_ansi());
+                std::thread::sleep(std::time::Duration::from_millis(delay));
+            }
+        }
+    }
     Ok(())
 }
 
@@ -1,127 +1,38 @@
 use std::{fs::read_to_string, path::PathBuf};
 
-use adventofcode_tooling::AocError;
-use hashbrown::{HashMap, HashSet};
+use adventofcode_2021::{
+    caves::{CaveError, CaveGraph, OneSmallTwice, PathError, SmallOnce},
+    options::Options,
+};
 
-#[must_use]
-fn part_1(data: &HashMap<String, HashSet<String>>) -> usize {
-    let mut visited_small_caves = [String::from("start")]
-        .iter()
-        .cloned()
-        .collect::<HashSet<_>>();
-    recursive_traversal(data, "start", &mut visited_small_caves)
+fn part_1(graph: &CaveGraph) -> Result<u64, PathError> {
+    graph.count_paths(&SmallOnce)
 }
 
-fn recursive_traversal(
-    data: &HashMap<String, HashSet<String>>,
-    current_cave: &str,
-    visited_small_caves: &mut HashSet<String>,
-) -> usize {
-    if current_cave == "end" {
-        visited_small_caves.remove("end");
-        return 1;
-    }
-
-    let mut res = 0;
-
-    if let Some(connected_caves) = data.get(current_cave) {
-        for connected_cave in connected_caves {
-            if is_small_cave(connected_cave) {
-                if visited_small_caves.contains(connected_cave) {
-                    continue;
-                }
-                visited_small_caves.insert(connected_cave.to_string());
-            }
-            res += recursive_traversal(data, connected_cave, visited_small_caves);
-            visited_small_caves.remove(connected_cave);
-        }
-    }
-    res
-}
-
-fn recursive_traversal_complex(
-    data: &HashMap<String, HashSet<String>>,
-    current_cave: &str,
-    visited_small_caves: &mut HashSet<String>,
-    twice_visited: &mut Option<String>,
-) -> usize {
-    if current_cave == "end" {
-        visited_small_caves.remove("end");
-        return 1;
-    }
-
-    let mut res = 0;
-
-    if let Some(connected_caves) = data.get(current_cave) {
-        for connected_cave in connected_caves {
-            if is_small_cave(connected_cave) {
-                if !visited_small_caves.contains(connected_cave) {
-                    visited_small_caves.insert(connected_cave.to_string());
-                } else if twice_visited.is_none()
-                    && connected_cave != "start"
-                    && connected_cave != "end"
-                {
-                    *twice_visited = Some(connected_cave.to_string());
-                } else {
-                    continue;
-                }
-            }
-
-            if let Some(val) = twice_visited {
-                res += recursive_traversal(data, connected_cave, visited_small_caves);
-                if val == connected_cave {
-                    *twice_visited = None;
-                }
-            } else {
-                res += recursive_traversal_complex(
-                    data,
-                    connected_cave,
-                    visited_small_caves,
-                    twice_visited,
-                );
-                visited_small_caves.remove(connected_cave);
-            }
-        }
-    }
-    res
-}
-
-#[must_use]
-fn part_2(data: &HashMap<String, HashSet<String>>) -> usize {
-    let mut visited_small_caves = [(String::from("start"))]
-        .iter()
-        .cloned()
-        .collect::<HashSet<_>>();
-
-    let mut twice_visited = None;
-    recursive_traversal_complex(data, "start", &mut visited_small_caves, &mut twice_visited)
+fn part_2(graph: &CaveGraph) -> Result<u64, PathError> {
+    graph.count_paths(&OneSmallTwice)
 }
 
-#[must_use]
-pub fn is_small_cave(cave: &str) -> bool {
-    cave.chars().all(|c| c.is_ascii_lowercase())
+fn parse_input<T: AsRef<str>>(data: T) -> Result<CaveGraph, CaveError> {
+    data.as_ref().parse()
 }
 
-fn parse_input<T: AsRef<str>>(data: T) -> HashMap<String, HashSet<String>> {
-    let mut hmap = HashMap::new();
-    for line in data.as_ref().lines() {
-        let parts: Vec<String> = line.split('-').map(|s| s.trim().to_string()).collect();
-        let a = parts[0].clone();
-        let b = parts[1].clone();
-        hmap.entry(a.clone())
-            .or_insert_with(HashSet::new)
-            .insert(b.clone());
-        hmap.entry(b).or_insert_with(HashSet::new).insert(a);
-    }
-    hmap
-}
+/// Paths listed by `--through` without `--paths`
+const DEFAULT_PATHS: usize = 10;
 
 /// Process solutions for day 12
 ///
 /// # Errors
 ///
-/// May fail if input data cannot be read
-pub fn main() -> Result<(), AocError> {
+/// May fail if input data cannot be read or holds a malformed passage
+///
+/// Options:
+/// * `--paths <n>`: list the first `n` paths of part 2
+/// * `--through <cave>`: only list paths going through `cave`, the first 10
+///   unless `--paths` is given
+/// * `--dot <path>`: write the caves as a Graphviz graph, highlighting the
+///   first path listed
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now = std::time::Instant::now();
     // Read file to a single string
     let mut filepath: PathBuf = std::env::current_dir().unwrap();
@@ -129,11 +40,34 @@ pub fn main() -> Result<(), AocError> {
     filepath.push("day_2021_12.data");
     let input_data = read_to_string(filepath).unwrap();
 
-    let data = parse_input(&input_data);
-    println!("Part 1: {:?}", part_1(&data));
-    println!("Part 2: {}", part_2(&data));
+    let data = parse_input(&input_data)?;
+    println!("Part 1: {:?}", part_1(&data)?);
+    println!("Part 2: {}", part_2(&data)?);
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
+
+    let options = Options::from_env();
+    let through = options.value("--through")?;
+    let dot = options.value("--dot")?;
+    let limit = match options.value("--paths")? {
+        Some(limit) => Some(limit.parse()?),
+        None => through.map(|_| DEFAULT_PATHS),
+    };
+    if limit.is_some() || dot.is_some() {
+        let mut paths = data.paths(&OneSmallTwice)?;
+        if let Some(name) = through {
+            paths = paths.through(data.id(name).ok_or(format!("unknown cave `{name}`"))?);
+        }
+        let limit = limit.unwrap_or(0);
+        let paths: Vec<_> = paths.take(limit.max(1)).collect::<Result<_, _>>()?;
+        for path in paths.iter().take(limit) {
+            println!("{}", data.format_path(path));
+        }
+        if let Some(dot) = dot {
+            let writer = std::io::BufWriter::new(std::fs::File::create(dot)?);
+            data.write_dot(writer, paths.first().map(Vec::as_slice))?;
+        }
+    }
     Ok(())
 }
 
@@ -152,8 +86,8 @@ b-d
 A-end
 b-end";
 
-        let input_data = parse_input(input_data);
-        assert_eq!(part_1(&input_data), 10);
+        let input_data = parse_input(input_data).unwrap();
+        assert_eq!(part_1(&input_data), Ok(10));
     }
 
     #[test]
@@ -166,7 +100,7 @@ b-d
 A-end
 b-end";
 
-        let input_data = parse_input(input_data);
-        assert_eq!(part_2(&input_data), 36);
+        let input_data = parse_input(input_data).unwrap();
+        assert_eq!(part_2(&input_data), Ok(36));
     }
 }
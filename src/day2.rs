@@ -1,4 +1,5 @@
-use adventofcode_2021::submarine::{Command, Direction};
+use adventofcode_2021::course::Script;
+use adventofcode_2021::submarine::{Command, Submarine};
 use adventofcode_tooling::{read_lines, AocError};
 
 /// Process data for a given step
@@ -8,24 +9,12 @@ use adventofcode_tooling::{read_lines, AocError};
 /// can't produce error
 #[must_use]
 pub fn part_1(data: &[Command]) -> isize {
-    let (horizontal, depth) = data
-        .iter()
-        .fold((0, 0), |(mut horizontal, mut depth), dir| {
-            match dir.direction {
-                Direction::Forward => {
-                    horizontal += dir.value;
-                }
-                Direction::Down => {
-                    depth += dir.value;
-                }
-                Direction::Up => {
-                    depth -= dir.value;
-                }
-            }
-
-            (horizontal, depth)
-        });
-    horizontal * depth
+    data.iter()
+        .fold(Submarine::new(), |mut submarine, command| {
+            submarine.apply(command);
+            submarine
+        })
+        .product()
 }
 
 /// Process data for a given step
@@ -35,24 +24,12 @@ pub fn part_1(data: &[Command]) -> isize {
 /// can't produce error
 #[must_use]
 pub fn part_2(data: &[Command]) -> isize {
-    let (horizontal, depth, _) =
-        data.iter()
-            .fold((0, 0, 0), |(mut horizontal, mut depth, mut aim), dir| {
-                match dir.direction {
-                    Direction::Forward => {
-                        horizontal += dir.value;
-                        depth += dir.value * aim;
-                    }
-                    Direction::Down => {
-                        aim += dir.value;
-                    }
-                    Direction::Up => {
-                        aim -= dir.value;
-                    }
-                }
-                (horizontal, depth, aim)
-            });
-    horizontal * depth
+    data.iter()
+        .fold(Submarine::new(), |mut submarine, command| {
+            submarine.apply_with_aim(command);
+            submarine
+        })
+        .product()
 }
 
 /// Process solutions for day 2
@@ -62,11 +39,14 @@ pub fn part_2(data: &[Command]) -> isize {
 /// May fail if input data cannot be read
 pub fn main() -> Result<(), AocError> {
     let now = std::time::Instant::now();
-    let values: Vec<_> = read_lines("day_2021_2.data")?
-        .map(Result::unwrap)
-        .map(|s| s.parse::<Command>())
+    // Plain command lists are valid course scripts too
+    let values = read_lines("day_2021_2.data")?
         .map(Result::unwrap)
-        .collect();
+        .collect::<Vec<_>>()
+        .join("\n")
+        .parse::<Script>()
+        .and_then(|script| script.expand())
+        .map_err(|_| AocError::ParsingError)?;
 
     println!("Part 1: {}", part_1(&values));
     println!("Part 2: {}", part_2(&values));
@@ -1,142 +1,41 @@
-use std::cmp::Ordering;
-
+use adventofcode_2021::diagnostic::{BitCriteria, Report, Word};
 use adventofcode_tooling::{read_lines, AocError};
 
-#[derive(Debug, Default, Clone)]
-struct BitCounter {
-    zeroes: usize,
-    ones: usize,
+fn parse_report<T: AsRef<str>>(data: &[T]) -> Result<Report, AocError> {
+    Report::from_lines(data).map_err(|_| AocError::ParsingError)
 }
 
-impl BitCounter {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    pub fn add_one(&mut self) {
-        self.ones += 1;
-    }
-
-    pub fn add_zero(&mut self) {
-        self.zeroes += 1;
-    }
-
-    pub fn decide_gamma(&self) -> u8 {
-        match self.zeroes.cmp(&self.ones) {
-            Ordering::Greater => 0,
-            _ => 1,
-        }
-    }
-
-    pub fn decide_alpha(&self) -> u8 {
-        match self.zeroes.cmp(&self.ones) {
-            Ordering::Greater => 1,
-            _ => 0,
-        }
-    }
+fn word_value(word: &Word) -> Result<isize, AocError> {
+    word.to_u64()
+        .and_then(|v| isize::try_from(v).ok())
+        .ok_or(AocError::ParsingError)
 }
 
 /// Process data for a given step
 ///
 /// # Errors
 ///
-/// can't produce error
+/// Fails if the report is invalid or if rates or their product do not fit in
+/// an `isize`
 pub fn part_1<T: AsRef<str>>(data: &[T]) -> Result<isize, AocError> {
-    let str_len = data[1].as_ref().len();
-    let mut initial_vec = vec![BitCounter::new(); str_len];
-    data.iter().map(std::convert::AsRef::as_ref).for_each(|s| {
-        s.chars().enumerate().for_each(|(pos, c)| {
-            if c == '0' {
-                initial_vec[pos].add_zero();
-            } else {
-                initial_vec[pos].add_one();
-            }
-        });
-    });
-
-    let gamma = evaluate(&initial_vec, BitCounter::decide_alpha);
-    let epsilon = evaluate(&initial_vec, BitCounter::decide_gamma);
-
-    Ok(gamma * epsilon)
-}
-
-fn evaluate(values: &[BitCounter], decider: impl Fn(&BitCounter) -> u8) -> isize {
-    values.iter().map(decider).fold(0, |mut acc, bit| {
-        acc <<= 1;
-        if bit == 1 {
-            acc += 1;
-        }
-        acc
-    })
-}
-
-pub enum Rule {
-    Oxygen,
-    Co2,
-}
-
-#[must_use]
-pub fn partitions(data: &[String], idx: usize, rule: &Rule) -> Vec<String> {
-    let (p0, p1) = (partitioner(data, idx, '0'), partitioner(data, idx, '1'));
-
-    match rule {
-        Rule::Oxygen => {
-            if p0.len() > p1.len() {
-                p0
-            } else {
-                p1
-            }
-        }
-        Rule::Co2 => {
-            if p1.len() < p0.len() {
-                p1
-            } else {
-                p0
-            }
-        }
-    }
-}
-
-fn partitioner(data: &[String], idx: usize, value: char) -> Vec<String> {
-    data.iter()
-        .filter(|s| s.chars().nth(idx) == Some(value))
-        .cloned()
-        .collect()
+    let report = parse_report(data)?;
+    word_value(&report.gamma())?
+        .checked_mul(word_value(&report.epsilon())?)
+        .ok_or(AocError::ParsingError)
 }
 
 /// Process data for a given step
 ///
 /// # Errors
 ///
-/// can't produce error
+/// Fails if the report is invalid or if ratings or their product do not fit
+/// in an `isize`
 pub fn part_2<T: AsRef<str>>(data: &[T]) -> Result<isize, AocError> {
-    let str_len = data[1].as_ref().len();
-
-    let mut result_oxygen = data
-        .iter()
-        .map(|s| s.as_ref().to_string())
-        .collect::<Vec<_>>();
-    for idx in 0..str_len {
-        if result_oxygen.len() != 1 {
-            result_oxygen = partitions(&result_oxygen, idx, &Rule::Oxygen);
-        }
-    }
+    let report = parse_report(data)?;
+    let oxygen = word_value(&report.rating(&BitCriteria::OXYGEN))?;
+    let co2 = word_value(&report.rating(&BitCriteria::CO2))?;
 
-    let mut result_co2 = data
-        .iter()
-        .map(|s| s.as_ref().to_string())
-        .collect::<Vec<_>>();
-
-    for idx in 0..str_len {
-        if result_co2.len() != 1 {
-            result_co2 = partitions(&result_co2, idx, &Rule::Co2);
-        }
-    }
-
-    let co2 = isize::from_str_radix(&result_co2[0], 2).map_err(|_| AocError::ParsingError)?;
-    let oxygen = isize::from_str_radix(&result_oxygen[0], 2).map_err(|_| AocError::ParsingError)?;
-
-    Ok(oxygen * co2)
+    oxygen.checked_mul(co2).ok_or(AocError::ParsingError)
 }
 
 /// Process solutions for day 3
@@ -178,4 +77,19 @@ mod tests {
         ];
         assert_eq!(part_2(values).unwrap(), 230);
     }
+
+    #[test]
+    fn test_day3_wide_words() {
+        let high = format!("10{}", "0".repeat(38));
+        let low = format!("01{}", "1".repeat(38));
+        let values = [high.clone(), high, low];
+        assert!(part_1(&values).is_err());
+        assert!(part_2(&values).is_err());
+
+        let values = [
+            format!("1{}", "0".repeat(39)),
+            format!("{}1", "0".repeat(39)),
+        ];
+        assert_eq!(part_2(&values).unwrap(), 1 << 39);
+    }
 }
@@ -1,157 +1,50 @@
 use std::path::PathBuf;
 
+use adventofcode_2021::bingo::{parse_game, BingoError, BingoEvent, BingoGame};
 use adventofcode_2021::Matrix2D;
 use adventofcode_tooling::AocError;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Mark {
-    Checked,
-    Unchecked,
-}
-
-type DayMatrix = Matrix2D<(usize, Mark)>;
-
-#[must_use]
-pub fn daymatrix_from_str(s: &str) -> DayMatrix {
-    let height = s.lines().count();
-
-    let values: Vec<_> = s
-        .lines()
-        .flat_map(str::split_whitespace)
-        .map(str::trim)
-        .map(str::parse)
-        .map(Result::unwrap)
-        .map(|v| (v, Mark::Unchecked))
-        .collect();
-
-    let width = values.len() / height;
-
-    DayMatrix {
-        width,
-        height,
-        values,
-    }
-}
-
-pub trait MarkMatrix {
-    fn mark_value_checked(&mut self, val: usize);
-    fn has_winning_row(&self) -> bool;
-    fn has_winning_col(&self) -> bool;
-    fn is_winning(&self) -> bool;
-    fn unmarked_sum(&self) -> usize;
-}
-
-impl MarkMatrix for DayMatrix {
-    fn mark_value_checked(&mut self, val: usize) {
-        if let Some(value) = self.values.iter_mut().find(|v| v.0 == val) {
-            *value = (val, Mark::Checked);
-        }
-    }
-
-    #[must_use]
-    fn has_winning_row(&self) -> bool {
-        self.rows()
-            .iter()
-            .any(|row| row.iter().all(|&(_, mark)| mark == Mark::Checked))
-    }
-
-    #[must_use]
-    fn has_winning_col(&self) -> bool {
-        self.cols()
-            .iter()
-            .any(|col| col.iter().all(|&(_, mark)| mark == Mark::Checked))
-    }
-
-    #[must_use]
-    fn is_winning(&self) -> bool {
-        self.has_winning_col() || self.has_winning_row()
-    }
+type DayMatrix = Matrix2D<usize>;
 
-    #[must_use]
-    fn unmarked_sum(&self) -> usize {
-        self.values
-            .iter()
-            .filter(|&(_, v)| v == &Mark::Unchecked)
-            .map(|(u, _)| u)
-            .sum()
+fn score(event: BingoEvent) -> usize {
+    match event {
+        BingoEvent::CardWon { score, .. } => score,
     }
 }
 
-/// Process data for a given step
+/// Score of the first card to win
 ///
 /// # Errors
 ///
-/// can't produce error
-pub fn part_1(draw: &[usize], cards: &mut [DayMatrix]) -> Result<usize, AocError> {
-    if draw.is_empty() {
-        return Err(AocError::ParsingError);
-    }
-    let mut last_draw = None;
-
-    for val in draw {
-        last_draw = Some(*val);
-        cards.iter_mut().for_each(|s| s.mark_value_checked(*val));
-        if cards.iter().any(DayMatrix::is_winning) {
-            break;
-        }
-    }
-
-    let matrix_found = cards
-        .iter()
-        .find(|c| c.is_winning())
-        .ok_or(AocError::ParsingError)?;
-
-    Ok(last_draw.ok_or(AocError::ParsingError)? * matrix_found.unmarked_sum())
+/// Fails if no card ever wins
+pub fn part_1(draw: &[usize], cards: &[DayMatrix]) -> Result<usize, AocError> {
+    BingoGame::new(cards)
+        .play(draw)
+        .next()
+        .map(score)
+        .ok_or(AocError::ParsingError)
 }
 
-// Process data for ap
+/// Score of the last card to win
 ///
 /// # Errors
 ///
-/// can't produce erro
-pub fn part_2(draw: &[usize], cards: &mut [DayMatrix]) -> Result<usize, AocError> {
-    let mut cards = cards.to_vec();
-    for val in draw {
-        cards.iter_mut().for_each(|s| s.mark_value_checked(*val));
-        cards = cards
-            .iter()
-            .filter(|c| !c.is_winning())
-            .map(|c| DayMatrix {
-                width: c.width,
-                height: c.height,
-                values: c.values.clone(),
-            })
-            .collect();
-        if cards.len() == 1 {
-            break;
-        }
-    }
-
-    let mut matrix_found: DayMatrix = cards.pop().ok_or(AocError::ParsingError)?;
-
-    let mut last_draw = None;
-    for val in draw {
-        last_draw = Some(*val);
-        matrix_found.mark_value_checked(*val);
-        if matrix_found.is_winning() {
-            break;
-        }
-    }
-
-    Ok(last_draw.ok_or(AocError::ParsingError)? * matrix_found.unmarked_sum())
+/// Fails if no card ever wins
+pub fn part_2(draw: &[usize], cards: &[DayMatrix]) -> Result<usize, AocError> {
+    BingoGame::new(cards)
+        .play(draw)
+        .last()
+        .map(score)
+        .ok_or(AocError::ParsingError)
 }
 
-#[must_use]
-pub fn extract_data(data: &str) -> (Vec<usize>, Vec<DayMatrix>) {
-    let blocks: Vec<_> = data.split("\n\n").collect();
-    let draw: Vec<usize> = blocks[0]
-        .split(',')
-        .map(str::parse)
-        .map(Result::unwrap)
-        .collect();
-
-    let cards: Vec<_> = blocks[1..].iter().map(|s| daymatrix_from_str(s)).collect();
-    (draw, cards)
+/// Parse the draw line and the cards
+///
+/// # Errors
+///
+/// Fails if the draw line or a card is invalid
+pub fn extract_data(data: &str) -> Result<(Vec<usize>, Vec<DayMatrix>), BingoError> {
+    parse_game(data)
 }
 
 /// Process solutions for day 3
@@ -168,10 +61,9 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let input_data = std::fs::read_to_string(filepath).unwrap();
 
-    let (draw, mut cards) = extract_data(&input_data);
-    let mut cards_clone = cards.clone();
-    println!("{}", part_1(&draw, &mut cards).unwrap());
-    println!("{}", part_2(&draw, &mut cards_clone).unwrap());
+    let (draw, cards) = extract_data(&input_data)?;
+    println!("{}", part_1(&draw, &cards).unwrap());
+    println!("{}", part_2(&draw, &cards).unwrap());
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
 
@@ -204,9 +96,9 @@ mod tests {
 22 11 13  6  5
  2  0 12  3  7";
 
-        let (draw, mut cards) = extract_data(data);
+        let (draw, cards) = extract_data(data).unwrap();
 
-        assert_eq!(part_1(&draw, &mut cards).unwrap(), 4512);
+        assert_eq!(part_1(&draw, &cards).unwrap(), 4512);
     }
 
     #[test]
@@ -231,8 +123,8 @@ mod tests {
 22 11 13  6  5
  2  0 12  3  7";
 
-        let (draw, mut cards) = extract_data(data);
+        let (draw, cards) = extract_data(data).unwrap();
 
-        assert_eq!(part_2(&draw, &mut cards).unwrap(), 1924);
+        assert_eq!(part_2(&draw, &cards).unwrap(), 1924);
     }
 }
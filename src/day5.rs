@@ -1,159 +1,9 @@
-use hashbrown::HashMap;
-use std::{fmt::Debug, str::FromStr};
+use adventofcode_2021::{
+    options::Options,
+    vents::{coverage, Endpoints, Heatmap, Segment, VentSweepAndPrune},
+};
+use adventofcode_tooling::read_lines_to_vec_t;
 
-use adventofcode_tooling::{read_lines_to_vec_t, AocError};
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct Point {
-    x: isize,
-    y: isize,
-}
-
-impl Point {
-    #[must_use]
-    pub fn new(x: isize, y: isize) -> Self {
-        Self { x, y }
-    }
-}
-
-impl FromStr for Point {
-    type Err = AocError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.split(',').collect();
-        let x = parse_part(&parts, 0)?;
-        let y = parse_part(&parts, 1)?;
-
-        Ok(Point::new(x, y))
-    }
-}
-
-fn parse_part<T: FromStr>(parts: &[&str], idx: usize) -> Result<T, AocError> {
-    parts
-        .get(idx)
-        .ok_or(AocError::ParsingError)?
-        .trim()
-        .parse()
-        .map_err(|_| AocError::ParsingError)
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct Segment {
-    pub a: Point,
-    pub b: Point,
-}
-
-impl FromStr for Segment {
-    type Err = AocError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<_> = s.split("->").collect();
-
-        let a = parse_part(&parts, 0)?;
-        let b = parse_part(&parts, 1)?;
-
-        Ok(Segment::new(a, b))
-    }
-}
-
-impl Segment {
-    #[must_use]
-    pub fn new(a: Point, b: Point) -> Self {
-        Self { a, b }
-    }
-
-    #[must_use]
-    pub fn is_horizontal(&self) -> bool {
-        self.a.y == self.b.y
-    }
-
-    #[must_use]
-    pub fn is_vertical(&self) -> bool {
-        self.a.x == self.b.x
-    }
-
-    #[must_use]
-    pub fn is_diagonal(&self) -> bool {
-        let d_y = self.a.y - self.b.y;
-        let d_x = self.a.x - self.b.x;
-        d_y.abs() == d_x.abs()
-    }
-
-    #[must_use]
-    pub fn is_horizontal_or_vertical(&self) -> bool {
-        self.is_horizontal() || self.is_vertical()
-    }
-
-    #[must_use]
-    pub fn is_horizontal_vertical_or_diagonal(&self) -> bool {
-        self.is_diagonal() || self.is_horizontal_or_vertical()
-    }
-
-    #[must_use]
-    pub fn points(&self) -> Vec<Point> {
-        let mut res = self.h_points();
-        res.extend(self.v_points());
-        res.extend(self.diag_points());
-        res
-    }
-
-    #[must_use]
-    pub fn h_points(&self) -> Vec<Point> {
-        if self.is_horizontal() {
-            let (min, max) = match self.a.x.cmp(&self.b.x) {
-                std::cmp::Ordering::Greater => (self.b.x, self.a.x),
-                _ => (self.a.x, self.b.x),
-            };
-
-            (min..=max).map(|x| Point::new(x, self.b.y)).collect()
-        } else {
-            vec![]
-        }
-    }
-
-    #[must_use]
-    pub fn v_points(&self) -> Vec<Point> {
-        if self.is_vertical() {
-            let (min, max) = match self.a.y.cmp(&self.b.y) {
-                std::cmp::Ordering::Greater => (self.b.y, self.a.y),
-                _ => (self.a.y, self.b.y),
-            };
-
-            (min..=max).map(|y| Point::new(self.b.x, y)).collect()
-        } else {
-            vec![]
-        }
-    }
-
-    #[must_use]
-    pub fn diag_points(&self) -> Vec<Point> {
-        if self.is_diagonal() {
-            let (min_x, max_x) = match self.a.x.cmp(&self.b.x) {
-                std::cmp::Ordering::Greater => (self.b.x, self.a.x),
-                _ => (self.a.x, self.b.x),
-            };
-            let (min_y, max_y) = match self.a.y.cmp(&self.b.y) {
-                std::cmp::Ordering::Greater => (self.b.y, self.a.y),
-                _ => (self.a.y, self.b.y),
-            };
-
-            let p = Point::new(min_x, min_y);
-            if p == self.a || p == self.b {
-                (min_x..=max_x)
-                    .zip(min_y..=max_y)
-                    .map(|(x, y)| Point::new(x, y))
-                    .collect()
-            } else {
-                (min_x..=max_x)
-                    .zip((min_y..=max_y).rev())
-                    .map(|(x, y)| Point::new(x, y))
-                    .collect()
-            }
-        } else {
-            vec![]
-        }
-    }
-}
 /// Process data for a given step
 ///
 /// # Errors
@@ -178,23 +28,44 @@ pub fn part_2(data: &[Segment]) -> usize {
 ///
 /// TBD
 pub fn process(data: &[Segment], seg_condition: impl Fn(&Segment) -> bool) -> usize {
-    let mut hmap: HashMap<_, usize> = HashMap::new();
-    data.iter()
-        .filter(|s| seg_condition(s))
-        .flat_map(Segment::points)
-        .for_each(|point| {
-            let pos = (point.x, point.y);
-            *hmap.entry(pos).or_default() += 1;
-        });
-    hmap.iter().filter(|&(_, val)| val.ge(&2)).count()
+    let segments: Vec<_> = data.iter().filter(|s| seg_condition(s)).copied().collect();
+    VentSweepAndPrune::new(&segments).intersections().points
+}
+
+/// Rasterised overlap count, along with the segments left out by `seg_condition`
+#[derive(Debug, PartialEq, Eq)]
+pub struct Survey {
+    pub overlaps: usize,
+    pub skipped: Vec<Segment>,
+}
+
+/// Count points covered twice or more once segments are rasterised, whatever their slope
+#[must_use]
+pub fn survey(
+    data: &[Segment],
+    seg_condition: impl Fn(&Segment) -> bool,
+    endpoints: Endpoints,
+) -> Survey {
+    let (kept, skipped): (Vec<Segment>, Vec<Segment>) = data.iter().partition(|s| seg_condition(s));
+    let overlaps = coverage(&kept, endpoints)
+        .values()
+        .filter(|&&count| count >= 2)
+        .count();
+    Survey { overlaps, skipped }
 }
 
 /// Process solutions for day 5
 ///
 /// # Errors
 ///
-/// May fail if input data cannot be read
-pub fn main() {
+/// May fail if input data cannot be read or the heatmap cannot be written
+///
+/// Options:
+/// * `--survey`: list the segments part 2 skips, and count overlaps of all segments
+/// * `--ascii`: print the overlap heatmap
+/// * `--pgm <path>`: write the heatmap as a greyscale image
+/// * `--straight` / `--diagonal`: only draw horizontal and vertical, or diagonal, vents
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now = std::time::Instant::now();
     let values: Vec<Segment> = read_lines_to_vec_t("day_2021_5.data");
 
@@ -203,11 +74,55 @@ pub fn main() {
 
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
+
+    let options = Options::from_env();
+    if options.flag("--survey") {
+        let skipped = survey(
+            &values,
+            Segment::is_horizontal_vertical_or_diagonal,
+            Endpoints::Inclusive,
+        )
+        .skipped;
+        println!("Segments skipped by part 2: {}", skipped.len());
+        for segment in &skipped {
+            println!(
+                "  {},{} -> {},{}",
+                segment.a.x, segment.a.y, segment.b.x, segment.b.y
+            );
+        }
+        println!(
+            "All segments: {}",
+            survey(&values, |_| true, Endpoints::Inclusive).overlaps
+        );
+    }
+    let seg_condition = |s: &Segment| {
+        if options.flag("--straight") {
+            s.is_horizontal_or_vertical()
+        } else if options.flag("--diagonal") {
+            s.is_diagonal()
+        } else {
+            true
+        }
+    };
+    let pgm_path = options.value("--pgm")?;
+
+    if options.flag("--ascii") || pgm_path.is_some() {
+        let heatmap = Heatmap::new(&values, seg_condition, Endpoints::Inclusive)?;
+        if options.flag("--ascii") {
+            print!("{heatmap}");
+        }
+        if let Some(path) = pgm_path {
+            heatmap.write_pgm(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adventofcode_2021::vents::Point;
+    use std::str::FromStr;
 
     #[test]
     fn test_day5_segment_from_str() {
@@ -256,4 +171,58 @@ mod tests {
             .collect();
         assert_eq!(part_2(&input), 12);
     }
+
+    #[test]
+    fn test_day5_survey() {
+        let values = r"0,9 -> 5,9
+8,0 -> 0,8
+0,0 -> 4,2
+4,1 -> 4,3";
+
+        let input: Vec<_> = values
+            .lines()
+            .map(|l| Segment::from_str(l).unwrap())
+            .collect();
+        let report = survey(
+            &input,
+            Segment::is_horizontal_vertical_or_diagonal,
+            Endpoints::Inclusive,
+        );
+        assert_eq!(report.skipped, vec![input[2]]);
+        assert_eq!(report.overlaps, 0);
+
+        let report = survey(&input, |_| true, Endpoints::Inclusive);
+        assert!(report.skipped.is_empty());
+        assert_eq!(report.overlaps, 1);
+        assert_eq!(survey(&input, |_| true, Endpoints::HalfOpen).overlaps, 0);
+    }
+
+    #[test]
+    fn test_day5_survey_and_process() {
+        let values = r"0,0 -> 4,2
+0,1 -> 4,1
+2,0 -> 2,3
+0,3 -> 6,0
+4,2 -> 8,4
+1,0 -> 3,3
+0,9 -> 5,9
+6,0 -> 0,6";
+
+        let input: Vec<_> = values
+            .lines()
+            .map(|l| Segment::from_str(l).unwrap())
+            .collect();
+        // Skewed vents draw more cells than the lattice points they go through
+        assert_eq!(survey(&input, |_| true, Endpoints::Inclusive).overlaps, 8);
+        assert_eq!(process(&input, |_| true), 6);
+        assert_eq!(
+            survey(
+                &input,
+                Segment::is_horizontal_vertical_or_diagonal,
+                Endpoints::Inclusive
+            )
+            .overlaps,
+            process(&input, Segment::is_horizontal_vertical_or_diagonal)
+        );
+    }
 }
@@ -1,41 +1,20 @@
-use std::collections::VecDeque;
-
+use adventofcode_2021::{
+    count::BigCount,
+    lanternfish::{Breeding, Population, PopulationError},
+    options::Options,
+};
 use adventofcode_tooling::read_single_string_to_t_vec;
 
-pub trait LanternfishGroup {
-    fn populate(&mut self, values: &[usize]);
-    fn turn(&mut self);
-    fn result(&self) -> u128;
-}
-
-impl LanternfishGroup for VecDeque<u128> {
-    fn populate(&mut self, values: &[usize]) {
-        for idx in 0..=8 {
-            self.push_back(values.iter().filter(|&&v| v == idx).count() as u128);
-        }
-    }
-
-    fn turn(&mut self) {
-        let first = self.pop_front().unwrap();
-        if let Some(v) = self.get_mut(6) {
-            *v += first;
-        }
-        self.push_back(first);
-    }
-
-    fn result(&self) -> u128 {
-        self.iter().sum()
-    }
-}
-
-#[must_use]
-pub fn process(values: &[usize], turns: usize) -> u128 {
-    let mut group = VecDeque::with_capacity(9);
-    group.populate(values);
-    for _ in 0..turns {
-        group.turn();
-    }
-    group.result()
+/// Fish count after `turns` days
+///
+/// # Errors
+///
+/// Fails on a timer above 8, or if the count overflows a `u128`
+pub fn process(values: &[usize], turns: u64) -> Result<u128, PopulationError> {
+    Population::<u128>::new(Breeding::LANTERNFISH, values)?
+        .after(turns)?
+        .total()
+        .ok_or(PopulationError::Overflow { days: turns })
 }
 
 /// Process solutions for day 1
@@ -43,16 +22,38 @@ pub fn process(values: &[usize], turns: usize) -> u128 {
 /// # Errors
 ///
 /// May fail if input data cannot be read
-pub fn main() {
+///
+/// Options:
+/// * `--days <n>`: also count fish after `n` days, with arbitrary precision
+/// * `--csv <path>`: write the population of every day up to day 256
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now = std::time::Instant::now();
 
     let values = read_single_string_to_t_vec("day_2021_6.data", ',');
 
-    println!("Part 1: {}", process(&values, 80));
-    println!("Part 2: {}", process(&values, 256));
+    println!("Part 1: {}", process(&values, 80)?);
+    println!("Part 2: {}", process(&values, 256)?);
+
+    let options = Options::from_env();
+    if let Some(days) = options.value("--days")? {
+        let days = days.parse()?;
+        let population =
+            Population::<BigCount>::new(Breeding::LANTERNFISH, &values)?.after(days)?;
+        if let Some(total) = population.total() {
+            println!("Day {days}: {total}");
+        }
+    }
+    if let Some(path) = options.value("--csv")? {
+        let timeline = Population::<u128>::new(Breeding::LANTERNFISH, &values)?.timeline(256)?;
+        if let Some(day) = timeline.first_day_exceeding(&1_000_000) {
+            println!("Over a million fish on day {day}");
+        }
+        timeline.write_csv(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+    }
 
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
+    Ok(())
 }
 
 #[cfg(test)]
@@ -64,12 +65,12 @@ mod tests {
     fn test_day6_part1() {
         let values = [3_usize, 4, 3, 1, 2];
 
-        assert_eq!(process(&values, 80), 5934);
+        assert_eq!(process(&values, 80).unwrap(), 5934);
     }
     #[test]
     fn test_day6_part2() {
         let values = [3_usize, 4, 3, 1, 2];
 
-        assert_eq!(process(&values, 256), 26984457539);
+        assert_eq!(process(&values, 256).unwrap(), 26984457539);
     }
 }
@@ -1,26 +1,28 @@
+use adventofcode_2021::{
+    crabs::{align, align_fleet, fuel_report, parse_fleet, CostModel},
+    options::Options,
+};
 use adventofcode_tooling::read_single_string_to_t_vec;
 
 #[must_use]
 pub fn part_1(values: &[isize]) -> Option<isize> {
-    process(values, |v1, v2| (v1 - v2).abs())
+    align(values, CostModel::Linear).map(|alignment| alignment.cost)
 }
 
 #[must_use]
 pub fn part_2(values: &[isize]) -> Option<isize> {
-    process(values, |v1, v2| (v1 - v2).abs() * ((v1 - v2).abs() + 1) / 2)
-}
-
-#[must_use]
-pub fn process(values: &[isize], distance: impl Fn(isize, isize) -> isize) -> Option<isize> {
-    let (&min, &max) = (values.iter().min()?, values.iter().max()?);
-    (min..=max)
-        .map(|idx| values.iter().map(|&val| distance(idx, val)).sum::<isize>())
-        .min()
+    align(values, CostModel::Triangular).map(|alignment| alignment.cost)
 }
 
 /// Process solutions for day 7
 ///
-pub fn main() {
+/// # Errors
+///
+/// May fail if the fleet file cannot be read or parsed
+///
+/// Options:
+/// * `--fleet <path>`: align a fleet of `position:weight:model` crabs
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     let now = std::time::Instant::now();
 
     // Read file to a single string
@@ -30,6 +32,20 @@ pub fn main() {
     println!("Part 2: {:?}", part_2(&values));
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
+
+    if let Some(path) = Options::from_env().value("--fleet")? {
+        let fleet = parse_fleet(&std::fs::read_to_string(path)?)?;
+        if let Some(alignment) = align_fleet(&fleet) {
+            println!(
+                "Fleet: position {}, cost {}",
+                alignment.position, alignment.cost
+            );
+            for (crab, fuel) in fleet.iter().zip(fuel_report(&fleet, alignment.position)) {
+                println!("  {crab}: {fuel}");
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,6 +1,19 @@
-use hashbrown::HashMap;
-
-use adventofcode_tooling::{read_lines, AocError};
+use adventofcode_2021::segments::{solve, GlyphTable, WiringError};
+use adventofcode_tooling::read_lines;
+use thiserror::Error;
+
+/// Errors raised while reading day 8 input lines
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReadingError {
+    #[error("no `|` between patterns and reading in `{0}`")]
+    MissingSeparator(String),
+    #[error("symbol `{0}` is not a decimal digit")]
+    NotDecimal(char),
+    #[error("reading `{0}` overflows")]
+    Overflow(String),
+    #[error(transparent)]
+    Wiring(#[from] WiringError),
+}
 
 #[must_use]
 pub fn part_1(values: &[String]) -> usize {
@@ -36,81 +49,6 @@ pub fn extract_str_len(input: &str, size: usize) -> Vec<Vec<char>> {
         .collect()
 }
 
-#[must_use]
-pub fn solve(input: &str) -> HashMap<String, usize> {
-    /*
-       If length is, number is:
-       * 2 => 1,
-       * 3 => 7,
-       * 4 => 4,
-       * 5 => case 5!
-       * 6 => case 6!
-       * 7 => 8
-
-       Case 5:
-       if 2 letters common with 1 => 3,
-       else if 3 letters common with 4 => 5,
-       else 2
-
-       Case 6:
-       if 'only 1 letter' common with 1 => 6
-       else if 4 letter common with 4 => 9,
-       else 0
-
-    */
-    let mut hmap = HashMap::new();
-    let s = input.split(' ').collect::<Vec<_>>();
-    let my_default = "";
-    let one = s.iter().find(|s| s.len() == 2).unwrap_or(&my_default);
-    let seven = s.iter().find(|s| s.len() == 3).unwrap_or(&my_default);
-    let four = s.iter().find(|s| s.len() == 4).unwrap_or(&my_default);
-    let eight = s.iter().find(|s| s.len() == 7).unwrap_or(&my_default);
-    hmap.insert(sorted_str(one), 1);
-    hmap.insert(sorted_str(seven), 7);
-    hmap.insert(sorted_str(four), 4);
-    hmap.insert(sorted_str(eight), 8);
-
-    let fives: Vec<_> = s.iter().filter(|s| s.len() == 5).collect();
-    for data in fives {
-        hmap.insert(sorted_str(data), is_three_five_two(data, one, four));
-    }
-
-    let sixs: Vec<_> = s.iter().filter(|s| s.len() == 6).collect();
-    for data in sixs {
-        hmap.insert(sorted_str(data), is_one_six_nine(data, one, four));
-    }
-    hmap
-}
-
-#[must_use]
-pub fn sorted_str<T: AsRef<str>>(s: T) -> String {
-    let mut s = s.as_ref().chars().collect::<Vec<char>>();
-    s.sort_unstable();
-    s.iter().collect()
-}
-
-#[must_use]
-pub fn is_one_six_nine(input: &str, one: &str, four: &str) -> usize {
-    if input.chars().filter(|&c| one.contains(c)).count() == 1 {
-        6
-    } else if input.chars().filter(|&c| four.contains(c)).count() == 4 {
-        9
-    } else {
-        0
-    }
-}
-
-#[must_use]
-pub fn is_three_five_two(input: &str, one: &str, four: &str) -> usize {
-    if input.chars().filter(|&c| one.contains(c)).count() == 2 {
-        3
-    } else if input.chars().filter(|&c| four.contains(c)).count() == 3 {
-        5
-    } else {
-        2
-    }
-}
-
 #[must_use]
 pub fn process(values: &[isize], distance: impl Fn(isize, isize) -> isize) -> Option<isize> {
     let (&min, &max) = (values.iter().min()?, values.iter().max()?);
@@ -124,36 +62,59 @@ pub fn process(values: &[isize], distance: impl Fn(isize, isize) -> isize) -> Op
 /// # Errors
 ///
 /// May fail if input data cannot be read
-pub fn main() -> Result<(), AocError> {
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Read file to a single string
     let now = std::time::Instant::now();
     let input_data: Vec<_> = read_lines("day_2021_8.data")?.map(Result::unwrap).collect();
 
     println!("Part 1: {:?}", part_1(&input_data));
-    println!("Part 2: {:?}", part_2(&input_data));
+    println!("Part 2: {:?}", part_2(&input_data)?);
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
     Ok(())
 }
 
-#[must_use]
-pub fn part_2(input: &[String]) -> usize {
-    input.iter().map(|s| solve_line(s)).sum()
+/// Sum of every decoded reading, whatever its length
+///
+/// # Errors
+///
+/// Fails if a line cannot be wired or decoded, or if a reading or the sum
+/// overflows
+pub fn part_2(input: &[String]) -> Result<usize, ReadingError> {
+    let table = GlyphTable::digits();
+    input.iter().try_fold(0_usize, |sum, s| {
+        let reading = solve_line(&table, s)?;
+        sum.checked_add(reading_value(&reading)?)
+            .ok_or(ReadingError::Overflow(reading))
+    })
 }
 
-#[must_use]
-fn solve_line(s: &str) -> usize {
-    let parts: Vec<_> = s.split('|').collect();
-    let dict = solve(parts[0]);
-    let res: Vec<_> = parts[1]
-        .split_ascii_whitespace()
-        .map(|s| {
-            let s = sorted_str(s);
-            dict.get(&s).unwrap()
-        })
-        .collect();
+/// Value of a decoded reading, read as a decimal number
+///
+/// # Errors
+///
+/// Fails on a symbol other than a decimal digit, or if the value overflows
+pub fn reading_value(reading: &str) -> Result<usize, ReadingError> {
+    reading.chars().try_fold(0_usize, |acc, c| {
+        let digit = c.to_digit(10).ok_or(ReadingError::NotDecimal(c))?;
+        acc.checked_mul(10)
+            .and_then(|acc| acc.checked_add(digit as usize))
+            .ok_or_else(|| ReadingError::Overflow(reading.to_string()))
+    })
+}
 
-    res[0] * 1000 + res[1] * 100 + res[2] * 10 + res[3]
+/// Decode the reading of a line, whatever its length
+///
+/// # Errors
+///
+/// Fails if the line has no `|` separator, or cannot be wired or decoded
+/// with `table`
+pub fn solve_line(table: &GlyphTable, s: &str) -> Result<String, ReadingError> {
+    let (patterns, reading) = s
+        .split_once('|')
+        .ok_or_else(|| ReadingError::MissingSeparator(s.to_string()))?;
+    let patterns: Vec<_> = patterns.split_ascii_whitespace().collect();
+    Ok(solve(table, &patterns)?.read(table, reading)?)
 }
 
 #[cfg(test)]
@@ -177,6 +138,29 @@ gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
         let values: Vec<_> = data.lines().map(std::string::ToString::to_string).collect();
 
         assert_eq!(part_1(&values), 26);
-        assert_eq!(part_2(&values), 61229);
+        assert_eq!(part_2(&values).unwrap(), 61229);
+    }
+
+    #[test]
+    fn test_day8_solve_line() {
+        let line = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf ab";
+        assert_eq!(
+            solve_line(&GlyphTable::digits(), line),
+            Ok("53531".to_string())
+        );
+        assert_eq!(
+            solve_line(&GlyphTable::digits(), "ab dab | ab"),
+            Err(ReadingError::Wiring(WiringError::Ambiguous))
+        );
+        assert_eq!(
+            solve_line(&GlyphTable::digits(), "ab dab"),
+            Err(ReadingError::MissingSeparator("ab dab".to_string()))
+        );
+        assert_eq!(part_2(&[line.to_string()]), Ok(53531));
+        assert_eq!(reading_value("A7Fb"), Err(ReadingError::NotDecimal('A')));
+        assert_eq!(
+            reading_value("99999999999999999999"),
+            Err(ReadingError::Overflow("99999999999999999999".to_string()))
+        );
     }
 }
@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-use adventofcode_2021::Matrix2D;
+use adventofcode_2021::{basins::BasinMap, Matrix2D};
 use adventofcode_tooling::{read_lines, AocError};
 
 #[must_use]
@@ -23,38 +23,9 @@ pub fn detect_low_points(values: &Matrix2D<usize>) -> impl Iterator<Item = (usiz
         })
 }
 
-pub fn part_2(values: &mut Matrix2D<usize>) -> usize {
-    let low_points = detect_low_points(values).collect::<Vec<_>>();
-    let mut sizes = low_points
-        .iter()
-        .map(|&(x_low, y_low)| dfs_point(values, x_low, y_low))
-        .collect::<Vec<_>>();
-    sizes.sort_unstable();
-    sizes.iter().rev().take(3).product()
-}
-
-pub fn dfs_point(values: &mut Matrix2D<usize>, x: usize, y: usize) -> usize {
-    let mut dfs = 1_usize;
-
-    values.values[x + y * values.width] = 10;
-
-    if y != 0 && values.neighbour_up(x, y).unwrap_or(9).lt(&9) {
-        dfs += dfs_point(values, x, y - 1);
-    }
-    // Then to the left
-    if x != 0 && values.neighbour_left(x, y).unwrap_or(9).lt(&9) {
-        dfs += dfs_point(values, x - 1, y);
-    }
-    // Then to the right
-    if values.neighbour_right(x, y).unwrap_or(9).lt(&9) {
-        dfs += dfs_point(values, x + 1, y);
-    }
-
-    // Then to the bottom
-    if values.neighbour_down(x, y).unwrap_or(9).lt(&9) {
-        dfs += dfs_point(values, x, y + 1);
-    }
-    dfs
+#[must_use]
+pub fn part_2(values: &Matrix2D<usize>) -> usize {
+    BasinMap::new(values, 9).sizes().iter().take(3).product()
 }
 
 /// Process solutions for day 1
@@ -66,19 +37,10 @@ pub fn main() -> Result<(), AocError> {
     let now = Instant::now();
     let input_data: Vec<String> = read_lines("day_2021_9.data")?.map(Result::unwrap).collect();
 
-    let (width, height) = (input_data.first().unwrap().len(), input_data.len());
-    let mut matrix = Matrix2D {
-        width,
-        height,
-        values: input_data
-            .iter()
-            .flat_map(|s| s.chars())
-            .map(|c| c.to_digit(10).unwrap() as usize)
-            .collect(),
-    };
+    let matrix = Matrix2D::from_digits(&input_data).ok_or(AocError::ParsingError)?;
 
     println!("Part 1: {:?}", part_1(&matrix));
-    println!("Part 2: {:?}", part_2(&mut matrix));
+    println!("Part 2: {:?}", part_2(&matrix));
     let elapsed = now.elapsed();
     println!("Exec time: {} \u{b5}s", elapsed.as_micros());
     Ok(())
@@ -127,7 +89,7 @@ mod tests {
             .collect::<Vec<String>>();
 
         let (width, height) = (input_data.first().unwrap().len(), input_data.len());
-        let mut matrix = Matrix2D {
+        let matrix = Matrix2D {
             width,
             height,
             values: input_data
@@ -136,6 +98,6 @@ mod tests {
                 .map(|c| c.to_digit(10).unwrap() as usize)
                 .collect(),
         };
-        assert_eq!(part_2(&mut matrix), 1134);
+        assert_eq!(part_2(&matrix), 1134);
     }
 }
@@ -0,0 +1,420 @@
+use std::{fmt::Display, str::FromStr};
+
+use thiserror::Error;
+
+const LIMB_BITS: usize = u64::BITS as usize;
+
+/// Errors raised while parsing a diagnostic report
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub enum DiagnosticError {
+    #[error("empty report")]
+    Empty,
+    #[error("line {line}: invalid character `{found}` at column {column}")]
+    InvalidChar {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    #[error("line {line}: expected {expected} bits, found {found}")]
+    RaggedLine {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// A binary word of arbitrary width.
+///
+/// Bits are packed in little-endian `u64` limbs: bit 0 of the first limb is
+/// the rightmost (least significant) character of the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Word {
+    width: usize,
+    limbs: Vec<u64>,
+}
+
+impl Word {
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Value of the bit in `column`, column 0 being the most significant
+    #[must_use]
+    pub fn bit(&self, column: usize) -> bool {
+        get_bit(&self.limbs, self.width - 1 - column)
+    }
+
+    /// The word value, if it fits in a `u64`
+    #[must_use]
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.limbs.iter().skip(1).all(|&limb| limb == 0) {
+            self.limbs.first().copied()
+        } else {
+            None
+        }
+    }
+
+    /// Flip every bit of the word, keeping its width
+    #[must_use]
+    pub fn complement(&self) -> Self {
+        let mut limbs: Vec<_> = self.limbs.iter().map(|limb| !limb).collect();
+        let spare = limbs.len() * LIMB_BITS - self.width;
+        if let Some(last) = limbs.last_mut() {
+            *last &= u64::MAX >> spare;
+        }
+        Self {
+            width: self.width,
+            limbs,
+        }
+    }
+}
+
+impl Display for Word {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for column in 0..self.width {
+            write!(f, "{}", if self.bit(column) { '1' } else { '0' })?;
+        }
+        Ok(())
+    }
+}
+
+fn get_bit(limbs: &[u64], position: usize) -> bool {
+    limbs[position / LIMB_BITS] >> (position % LIMB_BITS) & 1 == 1
+}
+
+/// Which bit value a criteria keeps for a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Commonness {
+    Most,
+    Least,
+}
+
+/// Order in which columns are considered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+/// Bit criteria used to build a word out of a report, column by column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitCriteria {
+    pub keep: Commonness,
+    /// Bit value kept when ones and zeroes are equally common
+    pub tie_break: bool,
+    pub order: BitOrder,
+}
+
+impl BitCriteria {
+    /// Oxygen generator rating: most common bit, ones winning ties
+    pub const OXYGEN: Self = Self::new(Commonness::Most, true);
+    /// CO2 scrubber rating: least common bit, zeroes winning ties
+    pub const CO2: Self = Self::new(Commonness::Least, false);
+
+    /// Criteria considering columns from the most significant one
+    #[must_use]
+    pub const fn new(keep: Commonness, tie_break: bool) -> Self {
+        Self {
+            keep,
+            tie_break,
+            order: BitOrder::MsbFirst,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_order(self, order: BitOrder) -> Self {
+        Self { order, ..self }
+    }
+
+    /// Bit value to keep for a column, given how many candidates have each value
+    #[must_use]
+    pub fn keep(self, ones: usize, zeroes: usize) -> bool {
+        match (ones.cmp(&zeroes), self.keep) {
+            (std::cmp::Ordering::Equal, _) => self.tie_break,
+            (std::cmp::Ordering::Greater, Commonness::Most)
+            | (std::cmp::Ordering::Less, Commonness::Least) => true,
+            _ => false,
+        }
+    }
+
+    /// Columns of a `width` bits word, in the order this criteria visits them
+    fn columns(self, width: usize) -> Box<dyn Iterator<Item = usize>> {
+        match self.order {
+            BitOrder::MsbFirst => Box::new(0..width),
+            BitOrder::LsbFirst => Box::new((0..width).rev()),
+        }
+    }
+}
+
+/// A diagnostic report: every line packed into the same number of limbs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    width: usize,
+    limbs: usize,
+    words: Vec<u64>,
+}
+
+impl Report {
+    /// Parse report lines made of `0` and `1`, skipping blank lines
+    ///
+    /// # Errors
+    ///
+    /// Fails if the report is empty, if a line holds something else than
+    /// binary digits, or if lines do not all have the same width
+    pub fn from_lines<I, T>(lines: I) -> Result<Self, DiagnosticError>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut report: Option<Self> = None;
+
+        for (idx, line) in lines.into_iter().enumerate() {
+            let line = line.as_ref().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let width = line.len();
+            let report = report.get_or_insert_with(|| Self {
+                width,
+                limbs: width.div_ceil(LIMB_BITS),
+                words: Vec::new(),
+            });
+            if width != report.width {
+                return Err(DiagnosticError::RaggedLine {
+                    line: idx + 1,
+                    expected: report.width,
+                    found: width,
+                });
+            }
+
+            let start = report.words.len();
+            report.words.resize(start + report.limbs, 0);
+            for (column, c) in line.chars().enumerate() {
+                let position = width - 1 - column;
+                match c {
+                    '0' => {}
+                    '1' => {
+                        report.words[start + position / LIMB_BITS] |= 1 << (position % LIMB_BITS);
+                    }
+                    found => {
+                        return Err(DiagnosticError::InvalidChar {
+                            line: idx + 1,
+                            column: column + 1,
+                            found,
+                        })
+                    }
+                }
+            }
+        }
+
+        report.ok_or(DiagnosticError::Empty)
+    }
+
+    /// Number of bits per line
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of lines
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.words.len() / self.limbs
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    fn limbs_of(&self, idx: usize) -> &[u64] {
+        &self.words[idx * self.limbs..(idx + 1) * self.limbs]
+    }
+
+    /// Line `idx` of the report
+    #[must_use]
+    pub fn word(&self, idx: usize) -> Word {
+        Word {
+            width: self.width,
+            limbs: self.limbs_of(idx).to_vec(),
+        }
+    }
+
+    /// Value of the bit in `column` for line `idx`, column 0 being the most significant
+    #[must_use]
+    pub fn bit(&self, idx: usize, column: usize) -> bool {
+        get_bit(self.limbs_of(idx), self.width - 1 - column)
+    }
+
+    /// Number of ones in every column, most significant column first
+    #[must_use]
+    pub fn column_counts(&self) -> Vec<usize> {
+        let mut counts = vec![0; self.width];
+        for word in self.words.chunks(self.limbs) {
+            for (limb_idx, &limb) in word.iter().enumerate() {
+                let mut bits = limb;
+                while bits != 0 {
+                    counts[limb_idx * LIMB_BITS + bits.trailing_zeros() as usize] += 1;
+                    bits &= bits - 1;
+                }
+            }
+        }
+        counts.reverse();
+        counts
+    }
+
+    /// Word made of the bit kept by `criteria` for every column, over all lines
+    #[must_use]
+    pub fn consensus(&self, criteria: &BitCriteria) -> Word {
+        let len = self.len();
+        let mut limbs = vec![0; self.limbs];
+        for (column, ones) in self.column_counts().into_iter().enumerate() {
+            if criteria.keep(ones, len - ones) {
+                let position = self.width - 1 - column;
+                limbs[position / LIMB_BITS] |= 1 << (position % LIMB_BITS);
+            }
+        }
+        Word {
+            width: self.width,
+            limbs,
+        }
+    }
+
+    /// Most common bit of every column, ones winning ties
+    #[must_use]
+    pub fn gamma(&self) -> Word {
+        self.consensus(&BitCriteria::OXYGEN)
+    }
+
+    /// Least common bit of every column, zeroes winning ties
+    #[must_use]
+    pub fn epsilon(&self) -> Word {
+        self.consensus(&BitCriteria::CO2)
+    }
+
+    /// Filter lines column by column following `criteria`, until one remains.
+    ///
+    /// Candidates are tracked as a list of line indices narrowed in place;
+    /// columns where every candidate agrees are left alone.
+    #[must_use]
+    pub fn rating(&self, criteria: &BitCriteria) -> Word {
+        let mut candidates: Vec<usize> = (0..self.len()).collect();
+        for column in criteria.columns(self.width) {
+            if candidates.len() <= 1 {
+                break;
+            }
+            let ones = candidates
+                .iter()
+                .filter(|&&idx| self.bit(idx, column))
+                .count();
+            let zeroes = candidates.len() - ones;
+            if ones == 0 || zeroes == 0 {
+                continue;
+            }
+            let keep = criteria.keep(ones, zeroes);
+            candidates.retain(|&idx| self.bit(idx, column) == keep);
+        }
+        self.word(candidates[0])
+    }
+
+    /// Product of the ratings given by every criteria, if it fits in a `u128`
+    #[must_use]
+    pub fn ratings_product(&self, criteria: &[BitCriteria]) -> Option<u128> {
+        criteria.iter().try_fold(1_u128, |acc, criteria| {
+            acc.checked_mul(u128::from(self.rating(criteria).to_u64()?))
+        })
+    }
+}
+
+impl FromStr for Report {
+    type Err = DiagnosticError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_lines(s.lines())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str =
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010";
+
+    #[test]
+    fn test_report_ratings() {
+        let report: Report = REPORT.parse().unwrap();
+        assert_eq!(report.len(), 12);
+        assert_eq!(report.column_counts(), vec![7, 5, 8, 7, 5]);
+        assert_eq!(report.gamma().to_u64(), Some(22));
+        assert_eq!(report.epsilon().to_u64(), Some(9));
+        assert_eq!(report.rating(&BitCriteria::OXYGEN).to_u64(), Some(23));
+        assert_eq!(report.rating(&BitCriteria::CO2).to_u64(), Some(10));
+    }
+
+    #[test]
+    fn test_custom_criteria() {
+        let report: Report = REPORT.parse().unwrap();
+        assert_eq!(
+            report.ratings_product(&[BitCriteria::OXYGEN, BitCriteria::CO2]),
+            Some(230)
+        );
+
+        // Least common bit, ones winning ties, from the least significant column
+        let scrubber = BitCriteria::new(Commonness::Least, true).with_order(BitOrder::LsbFirst);
+        assert_eq!(report.rating(&scrubber).to_string(), "10101");
+        assert_eq!(
+            report
+                .rating(&BitCriteria::OXYGEN.with_order(BitOrder::LsbFirst))
+                .to_string(),
+            "11110"
+        );
+    }
+
+    #[test]
+    fn test_wide_report() {
+        let wide = format!(
+            "1{}\n0{}1\n1{}",
+            "0".repeat(99),
+            "0".repeat(98),
+            "1".repeat(99)
+        );
+        let report: Report = wide.parse().unwrap();
+        assert_eq!(report.width(), 100);
+        assert_eq!(report.column_counts()[0], 2);
+        assert_eq!(report.column_counts()[99], 2);
+        assert_eq!(report.gamma().to_string(), format!("1{}1", "0".repeat(98)));
+        assert_eq!(report.epsilon().to_u64(), None);
+        assert_eq!(
+            report.rating(&BitCriteria::CO2).to_string(),
+            format!("0{}1", "0".repeat(98))
+        );
+
+        let single: Report = "101".parse().unwrap();
+        assert_eq!(single.gamma().to_u64(), Some(5));
+        assert_eq!(single.epsilon().to_u64(), Some(2));
+    }
+
+    #[test]
+    fn test_report_errors() {
+        assert_eq!("\n\n".parse::<Report>(), Err(DiagnosticError::Empty));
+        assert_eq!(
+            "101\n1021".parse::<Report>(),
+            Err(DiagnosticError::RaggedLine {
+                line: 2,
+                expected: 3,
+                found: 4
+            })
+        );
+        assert_eq!(
+            "101\n1x1".parse::<Report>(),
+            Err(DiagnosticError::InvalidChar {
+                line: 2,
+                column: 2,
+                found: 'x'
+            })
+        );
+    }
+}
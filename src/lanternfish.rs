@@ -0,0 +1,381 @@
+use std::{fmt::Display, io::Write};
+
+use thiserror::Error;
+
+use crate::{count::Count, Matrix2D};
+
+/// Errors raised while building or advancing a population
+#[derive(Debug, Clone, Copy, Error, PartialEq, Eq)]
+pub enum PopulationError {
+    #[error("timer {timer} is out of range, timers go from 0 to {max}")]
+    TimerOutOfRange { timer: usize, max: usize },
+    #[error("population count overflows after {days} days")]
+    Overflow { days: u64 },
+}
+
+/// Breeding parameters: the timer a fish goes back to after spawning, and the
+/// timer of a newborn fish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breeding {
+    pub reset: usize,
+    pub newborn: usize,
+}
+
+impl Breeding {
+    pub const LANTERNFISH: Self = Self {
+        reset: 6,
+        newborn: 8,
+    };
+
+    /// Number of timer buckets, from 0 to the highest of both timers
+    #[must_use]
+    pub const fn timers(self) -> usize {
+        if self.reset > self.newborn {
+            self.reset + 1
+        } else {
+            self.newborn + 1
+        }
+    }
+
+    /// One day as a matrix: `next = transition * current`
+    fn transition<T: Count>(self) -> Matrix2D<T> {
+        let size = self.timers();
+        let one = T::from_usize(1);
+        let mut values = vec![T::zero(); size * size];
+        for timer in 1..size {
+            values[(timer - 1) * size + timer] = one.clone();
+        }
+        values[self.reset * size] = T::from_usize(1);
+        values[self.newborn * size] = T::from_usize(1 + usize::from(self.reset == self.newborn));
+        Matrix2D {
+            width: size,
+            height: size,
+            values,
+        }
+    }
+}
+
+impl Default for Breeding {
+    fn default() -> Self {
+        Self::LANTERNFISH
+    }
+}
+
+fn checked_dot<'a, T: Count + 'a>(
+    lhs: impl Iterator<Item = &'a T>,
+    rhs: impl Iterator<Item = &'a T>,
+) -> Option<T> {
+    lhs.zip(rhs)
+        .try_fold(T::zero(), |acc, (a, b)| acc.checked_add(&a.checked_mul(b)?))
+}
+
+fn checked_mat_mul<T: Count>(lhs: &Matrix2D<T>, rhs: &Matrix2D<T>) -> Option<Matrix2D<T>> {
+    let size = lhs.width;
+    let mut values = Vec::with_capacity(size * size);
+    for row in 0..size {
+        for col in 0..size {
+            values.push(checked_dot(
+                lhs.values[row * size..(row + 1) * size].iter(),
+                rhs.values.iter().skip(col).step_by(size),
+            )?);
+        }
+    }
+    Some(Matrix2D {
+        width: size,
+        height: size,
+        values,
+    })
+}
+
+/// Fish counts per timer bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Population<T> {
+    breeding: Breeding,
+    counts: Vec<T>,
+}
+
+impl<T: Count> Population<T> {
+    /// Count the fish of each timer
+    ///
+    /// # Errors
+    ///
+    /// Fails if a timer is above both breeding timers
+    pub fn new(breeding: Breeding, timers: &[usize]) -> Result<Self, PopulationError> {
+        let size = breeding.timers();
+        let mut counts = vec![0_usize; size];
+        for &timer in timers {
+            *counts
+                .get_mut(timer)
+                .ok_or(PopulationError::TimerOutOfRange {
+                    timer,
+                    max: size - 1,
+                })? += 1;
+        }
+        Ok(Self {
+            breeding,
+            counts: counts.into_iter().map(T::from_usize).collect(),
+        })
+    }
+
+    #[must_use]
+    pub fn breeding(&self) -> Breeding {
+        self.breeding
+    }
+
+    /// Fish count of each timer, from timer 0
+    #[must_use]
+    pub fn counts(&self) -> &[T] {
+        &self.counts
+    }
+
+    /// Total fish count, `None` on overflow
+    #[must_use]
+    pub fn total(&self) -> Option<T> {
+        self.counts
+            .iter()
+            .try_fold(T::zero(), |acc, count| acc.checked_add(count))
+    }
+
+    /// Population a single day later, `None` on overflow
+    #[must_use]
+    pub fn step(&self) -> Option<Self> {
+        let mut counts = self.counts[1..].to_vec();
+        counts.push(T::zero());
+        let spawning = &self.counts[0];
+        for timer in [self.breeding.reset, self.breeding.newborn] {
+            counts[timer] = counts[timer].checked_add(spawning)?;
+        }
+        Some(Self {
+            breeding: self.breeding,
+            counts,
+        })
+    }
+
+    /// Population `days` days later, computed in O(log `days`) matrix products
+    ///
+    /// # Errors
+    ///
+    /// Fails if a count overflows, which may happen on the descendants of a
+    /// single fish even if the actual population fits
+    pub fn after(&self, days: u64) -> Result<Self, PopulationError> {
+        let overflow = PopulationError::Overflow { days };
+        let size = self.counts.len();
+
+        // Square and multiply, keeping `result = transition^(bits seen so far)`
+        let mut power = self.breeding.transition::<T>();
+        let mut result: Option<Matrix2D<T>> = None;
+        let mut remaining = days;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                result = Some(match result {
+                    Some(result) => checked_mat_mul(&result, &power).ok_or(overflow)?,
+                    None => power.clone(),
+                });
+            }
+            remaining >>= 1;
+            if remaining > 0 {
+                power = checked_mat_mul(&power, &power).ok_or(overflow)?;
+            }
+        }
+
+        let Some(result) = result else {
+            return Ok(self.clone());
+        };
+        let counts = (0..size)
+            .map(|row| {
+                checked_dot(
+                    result.values[row * size..(row + 1) * size].iter(),
+                    self.counts.iter(),
+                )
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(overflow)?;
+        Ok(Self {
+            breeding: self.breeding,
+            counts,
+        })
+    }
+
+    /// Every day from today to `days` days later, one day at a time
+    ///
+    /// # Errors
+    ///
+    /// Fails if a count or a total overflows
+    pub fn timeline(&self, days: u64) -> Result<Timeline<T>, PopulationError> {
+        let mut population = self.clone();
+        let mut timeline = Timeline {
+            breeding: self.breeding,
+            counts: Vec::new(),
+            totals: Vec::new(),
+        };
+        for day in 0..=days {
+            if day > 0 {
+                population = population
+                    .step()
+                    .ok_or(PopulationError::Overflow { days: day })?;
+            }
+            timeline.totals.push(
+                population
+                    .total()
+                    .ok_or(PopulationError::Overflow { days: day })?,
+            );
+            timeline.counts.push(population.counts.clone());
+        }
+        Ok(timeline)
+    }
+}
+
+/// Population history, from day 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline<T> {
+    breeding: Breeding,
+    counts: Vec<Vec<T>>,
+    totals: Vec<T>,
+}
+
+impl<T: Count> Timeline<T> {
+    /// Number of days, day 0 included
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.totals.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.totals.is_empty()
+    }
+
+    /// Total fish count of each day
+    #[must_use]
+    pub fn totals(&self) -> &[T] {
+        &self.totals
+    }
+
+    /// Fish count of each timer on `day`
+    #[must_use]
+    pub fn counts(&self, day: usize) -> Option<&[T]> {
+        self.counts.get(day).map(Vec::as_slice)
+    }
+
+    /// First day the population is strictly above `threshold`
+    #[must_use]
+    pub fn first_day_exceeding(&self, threshold: &T) -> Option<usize>
+    where
+        T: Ord,
+    {
+        self.totals.iter().position(|total| total > threshold)
+    }
+
+    /// Write one `day,total,timer_0,...` line per day, after a header line
+    ///
+    /// # Errors
+    ///
+    /// Fails if `writer` fails
+    pub fn write_csv(&self, mut writer: impl Write) -> std::io::Result<()>
+    where
+        T: Display,
+    {
+        write!(writer, "day,total")?;
+        for timer in 0..self.breeding.timers() {
+            write!(writer, ",timer_{timer}")?;
+        }
+        writeln!(writer)?;
+
+        for (day, (total, counts)) in self.totals.iter().zip(&self.counts).enumerate() {
+            write!(writer, "{day},{total}")?;
+            for count in counts {
+                write!(writer, ",{count}")?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::BigCount;
+
+    const SAMPLE: [usize; 5] = [3, 4, 3, 1, 2];
+
+    #[test]
+    fn test_after_matches_steps() {
+        for breeding in [
+            Breeding::LANTERNFISH,
+            Breeding {
+                reset: 2,
+                newborn: 4,
+            },
+            Breeding {
+                reset: 4,
+                newborn: 4,
+            },
+            Breeding {
+                reset: 5,
+                newborn: 3,
+            },
+        ] {
+            let mut stepped = Population::<u128>::new(breeding, &SAMPLE).unwrap();
+            for day in 0..40 {
+                assert_eq!(stepped.after(0).unwrap(), stepped);
+                let start = Population::<u128>::new(breeding, &SAMPLE).unwrap();
+                assert_eq!(start.after(day).unwrap(), stepped, "{breeding:?} day {day}");
+                stepped = stepped.step().unwrap();
+            }
+        }
+
+        let population = Population::<u128>::new(Breeding::LANTERNFISH, &SAMPLE).unwrap();
+        assert_eq!(population.after(80).unwrap().total(), Some(5934));
+        assert_eq!(population.after(256).unwrap().total(), Some(26_984_457_539));
+        assert_eq!(
+            Population::<u128>::new(Breeding::LANTERNFISH, &[9]),
+            Err(PopulationError::TimerOutOfRange { timer: 9, max: 8 })
+        );
+    }
+
+    #[test]
+    fn test_big_counts() {
+        let population = Population::<u128>::new(Breeding::LANTERNFISH, &SAMPLE).unwrap();
+        assert_eq!(
+            population.after(1_000_000),
+            Err(PopulationError::Overflow { days: 1_000_000 })
+        );
+
+        let small = population.after(256).unwrap().total().unwrap();
+        let big = Population::<BigCount>::new(Breeding::LANTERNFISH, &SAMPLE).unwrap();
+        assert_eq!(big.after(256).unwrap().total(), Some(BigCount::from(small)));
+        assert_eq!(
+            big.after(2000).unwrap().total().unwrap().to_string().len(),
+            77
+        );
+    }
+
+    #[test]
+    fn test_timeline() {
+        let population = Population::<u64>::new(Breeding::LANTERNFISH, &SAMPLE).unwrap();
+        let timeline = population.timeline(80).unwrap();
+        assert_eq!(timeline.len(), 81);
+        assert_eq!(timeline.totals()[18], 26);
+        assert_eq!(timeline.totals()[80], 5934);
+        assert_eq!(timeline.counts(2), Some(&[1, 2, 1, 0, 0, 0, 1, 0, 1][..]));
+        assert_eq!(timeline.counts(81), None);
+        assert_eq!(timeline.first_day_exceeding(&5), Some(2));
+        assert_eq!(timeline.first_day_exceeding(&5934), None);
+
+        let mut csv = Vec::new();
+        population.timeline(1).unwrap().write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8\n\
+             0,5,0,1,1,2,1,0,0,0,0\n\
+             1,5,1,1,2,1,0,0,0,0,0\n"
+        );
+        assert_eq!(
+            Population::<u64>::new(Breeding::LANTERNFISH, &SAMPLE)
+                .unwrap()
+                .timeline(1000),
+            Err(PopulationError::Overflow { days: 490 })
+        );
+    }
+}
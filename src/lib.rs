@@ -1,3 +1,17 @@
+pub mod basins;
+pub mod bingo;
+pub mod brackets;
+pub mod caves;
+pub mod count;
+pub mod course;
+pub mod crabs;
+pub mod diagnostic;
+pub mod lanternfish;
 pub mod matrix;
+pub mod octopus;
+pub mod options;
+pub mod segments;
+pub mod sonar;
 pub mod submarine;
+pub mod vents;
 pub use crate::matrix::Matrix2D;
@@ -1,4 +1,4 @@
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
 pub struct Matrix2D<T: Clone> {
     pub width: usize,
@@ -251,6 +251,129 @@ impl<T: Clone> Matrix2D<T> {
             values,
         }
     }
+
+    /// Cells connected to `start` whose value matches `predicate`, diagonals
+    /// included if `diags` is set.
+    ///
+    /// Returns `None` if `start` is out of the matrix or does not match.
+    #[must_use]
+    pub fn flood_fill(
+        &self,
+        start: (usize, usize),
+        diags: bool,
+        predicate: impl Fn(&T) -> bool,
+    ) -> Option<Component> {
+        if start.0 >= self.width || start.1 >= self.height {
+            return None;
+        }
+        let mut visited = vec![false; self.values.len()];
+        self.fill(start, diags, &predicate, &mut visited)
+    }
+
+    /// Every group of connected cells whose value matches `predicate`,
+    /// diagonals included if `diags` is set.
+    ///
+    /// Components come in reading order of their first cell.
+    #[must_use]
+    pub fn connected_components(
+        &self,
+        diags: bool,
+        predicate: impl Fn(&T) -> bool,
+    ) -> Vec<Component> {
+        let mut visited = vec![false; self.values.len()];
+        (0..self.values.len())
+            .filter_map(|idx| {
+                let start = (idx % self.width, idx / self.width);
+                self.fill(start, diags, &predicate, &mut visited)
+            })
+            .collect()
+    }
+
+    /// Iterative depth-first search from `start`, skipping `visited` cells
+    fn fill(
+        &self,
+        start: (usize, usize),
+        diags: bool,
+        predicate: &impl Fn(&T) -> bool,
+        visited: &mut [bool],
+    ) -> Option<Component> {
+        let start_idx = start.0 + start.1 * self.width;
+        if visited[start_idx] || !predicate(&self.values[start_idx]) {
+            return None;
+        }
+        visited[start_idx] = true;
+
+        let mut component = Component {
+            cells: Vec::new(),
+            min: start,
+            max: start,
+        };
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            component.push((x, y));
+            for (x, y) in self.get_neighbours_coord(x, y, diags) {
+                let idx = x + y * self.width;
+                if !visited[idx] && predicate(&self.values[idx]) {
+                    visited[idx] = true;
+                    stack.push((x, y));
+                }
+            }
+        }
+        Some(component)
+    }
+}
+
+impl<T: Clone + From<u8>> Matrix2D<T> {
+    /// One row per line of decimal digits, e.g. a height map.
+    ///
+    /// Returns `None` on a character other than a digit, or if lines do not
+    /// all have the same length.
+    pub fn from_digits<S: AsRef<str>>(lines: impl IntoIterator<Item = S>) -> Option<Self> {
+        let mut matrix = Self::new(0, 0);
+        for line in lines {
+            let line = line.as_ref();
+            if matrix.height > 0 && line.len() != matrix.width {
+                return None;
+            }
+            matrix.width = line.len();
+            matrix.height += 1;
+            for c in line.chars() {
+                let digit = u8::try_from(c.to_digit(10)?).ok()?;
+                matrix.values.push(T::from(digit));
+            }
+        }
+        Some(matrix)
+    }
+}
+
+/// Connected cells of a `Matrix2D`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// Cells as `(x, y)`, in the order they were reached
+    pub cells: Vec<(usize, usize)>,
+    /// Top left corner of the bounding box
+    pub min: (usize, usize),
+    /// Bottom right corner of the bounding box, included
+    pub max: (usize, usize),
+}
+
+impl Component {
+    fn push(&mut self, (x, y): (usize, usize)) {
+        self.cells.push((x, y));
+        self.min = (self.min.0.min(x), self.min.1.min(y));
+        self.max = (self.max.0.max(x), self.max.1.max(y));
+    }
+
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Width and height of the bounding box
+    #[must_use]
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.max.0 - self.min.0 + 1, self.max.1 - self.min.1 + 1)
+    }
 }
 
 #[cfg(test)]
@@ -306,4 +429,49 @@ mod tests {
         assert_eq!(matrix.neighbour(0, 0, true), vec![1, 4, 5]);
         assert_eq!(matrix.neighbour(1, 1, false), vec![4, 6, 1, 9]);
     }
+
+    #[test]
+    fn test_from_digits() {
+        let matrix = Matrix2D::<usize>::from_digits("219\n398".lines()).unwrap();
+        assert_eq!((matrix.width, matrix.height), (3, 2));
+        assert_eq!(matrix.values, vec![2, 1, 9, 3, 9, 8]);
+        assert_eq!(Matrix2D::<u8>::from_digits(["12", "3"]), None);
+        assert_eq!(Matrix2D::<u8>::from_digits(["1a"]), None);
+        assert_eq!(
+            Matrix2D::<u8>::from_digits(Vec::<String>::new()),
+            Some(Matrix2D::new(0, 0))
+        );
+    }
+
+    #[test]
+    fn test_components() {
+        let matrix = Matrix2D {
+            width: 5,
+            height: 4,
+            values: "##..#\
+                     #..#.\
+                     ...#.\
+                     ##.#."
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<Vec<_>>(),
+        };
+
+        let straight = matrix.connected_components(false, |&c| c == '#');
+        let sizes: Vec<_> = straight.iter().map(Component::size).collect();
+        assert_eq!(sizes, vec![3, 1, 3, 2]);
+        assert_eq!(straight[2].min, (3, 1));
+        assert_eq!(straight[2].max, (3, 3));
+        assert_eq!(straight[2].dimensions(), (1, 3));
+
+        let diagonal = matrix.connected_components(true, |&c| c == '#');
+        let sizes: Vec<_> = diagonal.iter().map(Component::size).collect();
+        assert_eq!(sizes, vec![3, 4, 2]);
+
+        let dots = matrix.flood_fill((2, 0), false, |&c| c == '.').unwrap();
+        assert_eq!(dots.size(), 8);
+        assert_eq!((dots.min, dots.max), ((0, 0), (3, 3)));
+        assert_eq!(matrix.flood_fill((0, 0), false, |&c| c == '.'), None);
+        assert_eq!(matrix.flood_fill((5, 0), false, |&c| c == '.'), None);
+    }
 }
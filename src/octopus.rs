@@ -0,0 +1,347 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    io::Write,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+
+use crate::Matrix2D;
+
+/// Clear the terminal and move the cursor to its top left corner
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// An octopus that flashed, and the cascade wave it flashed in (0 for the
+/// octopuses whose own energy went over the threshold)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flash {
+    pub cell: (usize, usize),
+    pub wave: usize,
+}
+
+/// What happened during a step
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StepReport {
+    /// Flashes, wave by wave
+    pub flashes: Vec<Flash>,
+    /// Number of cascade waves, 0 if nothing flashed
+    pub waves: usize,
+    /// Did every octopus flash during this step?
+    pub all_flashed: bool,
+}
+
+impl StepReport {
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.flashes.len()
+    }
+}
+
+/// A grid of octopuses of any size, flashing once over `threshold`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OctopusGrid {
+    energy: Matrix2D<u8>,
+    threshold: u8,
+}
+
+impl OctopusGrid {
+    #[must_use]
+    pub fn new(energy: Matrix2D<u8>) -> Self {
+        Self {
+            energy,
+            threshold: 9,
+        }
+    }
+
+    #[must_use]
+    pub fn with_threshold(self, threshold: u8) -> Self {
+        Self { threshold, ..self }
+    }
+
+    #[must_use]
+    pub fn energy(&self) -> &Matrix2D<u8> {
+        &self.energy
+    }
+
+    #[must_use]
+    pub fn into_energy(self) -> Matrix2D<u8> {
+        self.energy
+    }
+
+    /// Raise every energy level, then let flashes propagate.
+    ///
+    /// Flashing octopuses are queued, so every cell is only looked at when a
+    /// neighbour flashes rather than rescanning the grid for each wave.
+    pub fn step(&mut self) -> StepReport {
+        let width = self.energy.width;
+        let mut flashed = vec![false; self.energy.values.len()];
+        let mut queue = VecDeque::new();
+
+        for (idx, energy) in self.energy.values.iter_mut().enumerate() {
+            *energy = energy.saturating_add(1);
+            if *energy > self.threshold {
+                flashed[idx] = true;
+                queue.push_back(Flash {
+                    cell: (idx % width, idx / width),
+                    wave: 0,
+                });
+            }
+        }
+
+        let mut report = StepReport::default();
+        while let Some(flash) = queue.pop_front() {
+            report.waves = report.waves.max(flash.wave + 1);
+            report.flashes.push(flash);
+
+            let (x, y) = flash.cell;
+            for (x, y) in self.energy.get_neighbours_coord(x, y, true) {
+                let idx = x + y * width;
+                if flashed[idx] {
+                    continue;
+                }
+                let energy = &mut self.energy.values[idx];
+                *energy = energy.saturating_add(1);
+                if *energy > self.threshold {
+                    flashed[idx] = true;
+                    queue.push_back(Flash {
+                        cell: (x, y),
+                        wave: flash.wave + 1,
+                    });
+                }
+            }
+        }
+
+        for flash in &report.flashes {
+            self.energy.values[flash.cell.0 + flash.cell.1 * width] = 0;
+        }
+        report.all_flashed = report.count() == self.energy.values.len();
+        report
+    }
+
+    /// Record the grid over `steps`, step 0 being the current state. The grid
+    /// is left at step `steps.end()`.
+    pub fn record(&mut self, steps: RangeInclusive<usize>) -> Recording {
+        let mut frames = Vec::new();
+        if *steps.start() == 0 {
+            frames.push(Frame {
+                step: 0,
+                energy: self.energy.clone(),
+                flashed: Vec::new(),
+            });
+        }
+        for step in 1..=*steps.end() {
+            let report = self.step();
+            if steps.contains(&step) {
+                frames.push(Frame {
+                    step,
+                    energy: self.energy.clone(),
+                    flashed: report.flashes.iter().map(|flash| flash.cell).collect(),
+                });
+            }
+        }
+        Recording {
+            threshold: self.threshold,
+            frames,
+        }
+    }
+}
+
+/// Energy levels after a step, along with the octopuses that flashed during it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub step: usize,
+    pub energy: Matrix2D<u8>,
+    pub flashed: Vec<(usize, usize)>,
+}
+
+impl Frame {
+    /// Frame as terminal text, flashing octopuses in bold yellow.
+    ///
+    /// Energy levels over 9 are written as letters.
+    #[must_use]
+    pub fn to_ansi(&self) -> String {
+        let mut flashed = vec![false; self.energy.values.len()];
+        for &(x, y) in &self.flashed {
+            flashed[x + y * self.energy.width] = true;
+        }
+
+        let mut output = format!("step {}: {} flashes\n", self.step, self.flashed.len());
+        for (row, flashed) in self
+            .energy
+            .values
+            .chunks(self.energy.width.max(1))
+            .zip(flashed.chunks(self.energy.width.max(1)))
+        {
+            for (&energy, &flashed) in row.iter().zip(flashed) {
+                let c = char::from_digit(u32::from(energy), 36).unwrap_or('+');
+                if flashed {
+                    let _ = write!(output, "\x1b[1;93m{c}\x1b[0m");
+                } else {
+                    output.push(c);
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Write the frame as a plain PGM image, flashing octopuses at `white`
+    /// and the others at their energy level
+    ///
+    /// # Errors
+    ///
+    /// Fails if `writer` does
+    pub fn write_pgm(&self, mut writer: impl Write, white: u8) -> std::io::Result<()> {
+        writeln!(writer, "P2")?;
+        writeln!(writer, "# step {}", self.step)?;
+        writeln!(writer, "{} {}", self.energy.width, self.energy.height)?;
+        writeln!(writer, "{white}")?;
+
+        let mut levels: Vec<_> = self
+            .energy
+            .values
+            .iter()
+            .map(|&energy| energy.min(white))
+            .collect();
+        for &(x, y) in &self.flashed {
+            levels[x + y * self.energy.width] = white;
+        }
+        for row in levels.chunks(self.energy.width.max(1)) {
+            writeln!(writer, "{}", row.iter().join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Frames recorded over a range of steps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    pub threshold: u8,
+    pub frames: Vec<Frame>,
+}
+
+impl Recording {
+    /// Write every frame as terminal text, each one clearing the screen first
+    ///
+    /// # Errors
+    ///
+    /// Fails if `writer` does
+    pub fn write_ansi(&self, mut writer: impl Write) -> std::io::Result<()> {
+        for frame in &self.frames {
+            write!(writer, "{CLEAR_SCREEN}{}", frame.to_ansi())?;
+        }
+        Ok(())
+    }
+
+    /// Write each frame to `dir` as `step_NNNN.pgm`, returning the paths written.
+    /// `dir` is created if missing.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `dir` or a file cannot be created
+    pub fn write_pgm(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(dir)?;
+        let white = self.threshold.saturating_add(1);
+        let mut paths = Vec::new();
+        for frame in &self.frames {
+            let path = dir.join(format!("step_{:04}.pgm", frame.step));
+            frame.write_pgm(
+                std::io::BufWriter::new(std::fs::File::create(&path)?),
+                white,
+            )?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(input: &str) -> Matrix2D<u8> {
+        Matrix2D::from_digits(input.lines()).unwrap()
+    }
+
+    #[test]
+    fn test_waves() {
+        let mut octopuses = OctopusGrid::new(grid("11111\n19991\n19191\n19991\n11111"));
+
+        let report = octopuses.step();
+        assert_eq!(report.count(), 9);
+        assert_eq!(report.waves, 2);
+        assert_eq!(
+            report
+                .flashes
+                .iter()
+                .filter(|flash| flash.wave == 1)
+                .count(),
+            1
+        );
+        assert_eq!(
+            report.flashes.last(),
+            Some(&Flash {
+                cell: (2, 2),
+                wave: 1
+            })
+        );
+        assert!(!report.all_flashed);
+        assert_eq!(
+            octopuses.energy().values,
+            grid("34543\n40004\n50005\n40004\n34543").values
+        );
+
+        let report = octopuses.step();
+        assert_eq!((report.count(), report.waves), (0, 0));
+    }
+
+    #[test]
+    fn test_threshold_and_full_flash() {
+        let mut octopuses = OctopusGrid::new(grid("123\n456")).with_threshold(4);
+        let report = octopuses.step();
+        assert_eq!(report.count(), 6);
+        assert!(report.all_flashed);
+        assert_eq!(octopuses.into_energy().values, vec![0; 6]);
+    }
+
+    #[test]
+    fn test_record() {
+        let mut octopuses = OctopusGrid::new(grid("11111\n19991\n19191\n19991\n11111"));
+        let recording = octopuses.record(1..=2);
+        assert_eq!(
+            recording
+                .frames
+                .iter()
+                .map(|frame| frame.step)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(recording.frames[0].flashed.len(), 9);
+        assert_eq!(&recording.frames[1].energy, octopuses.energy());
+
+        let ansi = recording.frames[0].to_ansi();
+        assert!(ansi.starts_with("step 1: 9 flashes\n34543\n4\x1b[1;93m0\x1b[0m"));
+
+        let mut pgm = Vec::new();
+        recording.frames[1].write_pgm(&mut pgm, 10).unwrap();
+        assert_eq!(
+            String::from_utf8(pgm).unwrap(),
+            "P2\n# step 2\n5 5\n10\n4 5 6 5 4\n5 1 1 1 5\n6 1 1 1 6\n5 1 1 1 5\n4 5 6 5 4\n"
+        );
+
+        let mut playback = Vec::new();
+        OctopusGrid::new(grid("12\n34"))
+            .record(0..=3)
+            .write_ansi(&mut playback)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(playback)
+                .unwrap()
+                .matches(CLEAR_SCREEN)
+                .count(),
+            4
+        );
+    }
+}
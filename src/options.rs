@@ -0,0 +1,75 @@
+use thiserror::Error;
+
+/// Errors raised while reading command line options
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum OptionError {
+    #[error("option `{0}` expects a value")]
+    MissingValue(String),
+}
+
+/// Command line options: `--flag` switches and `--name value` pairs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Options {
+    args: Vec<String>,
+}
+
+impl Options {
+    #[must_use]
+    pub fn new(args: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            args: args.into_iter().collect(),
+        }
+    }
+
+    /// Options of the running program, its name left out
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::new(std::env::args().skip(1))
+    }
+
+    #[must_use]
+    pub fn flag(&self, name: &str) -> bool {
+        self.args.iter().any(|arg| arg == name)
+    }
+
+    /// Value following `name`, `None` if the option is not given
+    ///
+    /// # Errors
+    ///
+    /// Fails if `name` is the last argument or is followed by another option
+    pub fn value(&self, name: &str) -> Result<Option<&str>, OptionError> {
+        let Some(idx) = self.args.iter().position(|arg| arg == name) else {
+            return Ok(None);
+        };
+        match self.args.get(idx + 1) {
+            Some(value) if !value.starts_with("--") => Ok(Some(value)),
+            _ => Err(OptionError::MissingValue(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options() {
+        let options = Options::new(
+            ["--ascii", "--from", "-3", "--pgm", "--to"]
+                .into_iter()
+                .map(String::from),
+        );
+        assert!(options.flag("--ascii"));
+        assert!(!options.flag("--animate"));
+        assert_eq!(options.value("--from"), Ok(Some("-3")));
+        assert_eq!(options.value("--delay"), Ok(None));
+        assert_eq!(
+            options.value("--pgm"),
+            Err(OptionError::MissingValue("--pgm".to_string()))
+        );
+        assert_eq!(
+            options.value("--to"),
+            Err(OptionError::MissingValue("--to".to_string()))
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WiringError {
+    #[error("invalid wire `{0}`")]
+    InvalidWire(char),
+    #[error("no wiring matches the observed patterns")]
+    Inconsistent,
+    #[error("several wirings match the observed patterns")]
+    Ambiguous,
+    #[error("pattern `{0}` is not a known symbol")]
+    UnknownPattern(String),
+    #[error("displays have between 1 and 32 segments, not {0}")]
+    InvalidSegmentCount(usize),
+    #[error("symbols `{0}` and `{1}` light the same segments")]
+    DuplicateGlyph(char, char),
+}
+
+/// Set of wires (or segments) as a bit mask, `a` being the lowest bit
+///
+/// # Errors
+///
+/// Fails on a character outside of the first `segments` letters
+pub fn wire_set(pattern: &str, segments: usize) -> Result<u32, WiringError> {
+    pattern.chars().try_fold(0, |set, wire| {
+        let idx = u32::from(wire)
+            .checked_sub(u32::from('a'))
+            .filter(|&idx| (idx as usize) < segments)
+            .ok_or(WiringError::InvalidWire(wire))?;
+        Ok(set | 1 << idx)
+    })
+}
+
+fn name(idx: usize) -> char {
+    u8::try_from(idx).map_or('?', |idx| char::from(b'a' + idx))
+}
+
+const DIGITS: [(char, &str); 10] = [
+    ('0', "abcefg"),
+    ('1', "cf"),
+    ('2', "acdeg"),
+    ('3', "acdfg"),
+    ('4', "bcdf"),
+    ('5', "abdfg"),
+    ('6', "abdefg"),
+    ('7', "acf"),
+    ('8', "abcdefg"),
+    ('9', "abcdfg"),
+];
+
+const HEX_LETTERS: [(char, &str); 6] = [
+    ('A', "abcdef"),
+    ('b', "bdefg"),
+    ('C', "abeg"),
+    ('d', "cdefg"),
+    ('E', "abdeg"),
+    ('F', "abde"),
+];
+
+/// Segments lit by each symbol of a display, segments being named from `a`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphTable {
+    segments: usize,
+    glyphs: Vec<(char, u32)>,
+}
+
+impl GlyphTable {
+    /// Build a table from `(symbol, lit segments)` pairs, e.g. `('1', "cf")`
+    ///
+    /// # Errors
+    ///
+    /// Fails on more than 32 segments, on an invalid segment, or if two symbols
+    /// light the same segments
+    pub fn new(segments: usize, glyphs: &[(char, &str)]) -> Result<Self, WiringError> {
+        if segments == 0 || segments > 32 {
+            return Err(WiringError::InvalidSegmentCount(segments));
+        }
+        let glyphs = glyphs
+            .iter()
+            .map(|&(symbol, lit)| Ok((symbol, wire_set(lit, segments)?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self { segments, glyphs }.checked()
+    }
+
+    /// Decimal digits on a seven-segment display
+    #[must_use]
+    pub fn digits() -> Self {
+        Self::new(7, &DIGITS).unwrap_or_else(|_| unreachable!())
+    }
+
+    /// Hexadecimal digits on a seven-segment display: `0-9`, then `AbCdEF`
+    #[must_use]
+    pub fn hex_digits() -> Self {
+        let glyphs: Vec<_> = DIGITS.iter().chain(&HEX_LETTERS).copied().collect();
+        Self::new(7, &glyphs).unwrap_or_else(|_| unreachable!())
+    }
+
+    /// The same table on a faulty display, where some segments are always lit
+    /// and some never are
+    ///
+    /// # Errors
+    ///
+    /// Fails on an invalid segment, or if two symbols can no longer be told apart
+    pub fn with_stuck(self, lit: &str, dark: &str) -> Result<Self, WiringError> {
+        let lit = wire_set(lit, self.segments)?;
+        let dark = wire_set(dark, self.segments)?;
+        Self {
+            glyphs: self
+                .glyphs
+                .iter()
+                .map(|&(symbol, glyph)| (symbol, (glyph | lit) & !dark))
+                .collect(),
+            ..self
+        }
+        .checked()
+    }
+
+    fn checked(self) -> Result<Self, WiringError> {
+        for (idx, &(first, glyph)) in self.glyphs.iter().enumerate() {
+            if let Some(&(second, _)) = self.glyphs[idx + 1..].iter().find(|&&(_, g)| g == glyph) {
+                return Err(WiringError::DuplicateGlyph(first, second));
+            }
+        }
+        Ok(self)
+    }
+
+    #[must_use]
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    /// Symbol shown when `lit` segments are on
+    #[must_use]
+    pub fn symbol(&self, lit: u32) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|&&(_, glyph)| glyph == lit)
+            .map(|&(symbol, _)| symbol)
+    }
+}
+
+/// Which segment each wire lights
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Wiring {
+    segments: Vec<usize>,
+}
+
+impl Wiring {
+    /// Segment lit by `wire`
+    #[must_use]
+    pub fn segment(&self, wire: char) -> Option<char> {
+        let idx = wire_set(&wire.to_string(), self.segments.len())
+            .ok()?
+            .trailing_zeros() as usize;
+        Some(name(self.segments[idx]))
+    }
+
+    /// Segments lit by a set of wires
+    #[must_use]
+    pub fn map(&self, wires: u32) -> u32 {
+        (0..self.segments.len())
+            .filter(|&wire| wires & 1 << wire != 0)
+            .fold(0, |set, wire| set | 1 << self.segments[wire])
+    }
+
+    /// Symbol shown by a pattern of wires
+    ///
+    /// # Errors
+    ///
+    /// Fails on an invalid wire or if lit segments are not a symbol of `table`
+    pub fn decode(&self, table: &GlyphTable, pattern: &str) -> Result<char, WiringError> {
+        table
+            .symbol(self.map(wire_set(pattern, self.segments.len())?))
+            .ok_or_else(|| WiringError::UnknownPattern(pattern.to_string()))
+    }
+
+    /// Symbols shown by whitespace separated patterns, whatever their count
+    ///
+    /// # Errors
+    ///
+    /// Fails if a pattern cannot be decoded
+    pub fn read(&self, table: &GlyphTable, reading: &str) -> Result<String, WiringError> {
+        reading
+            .split_whitespace()
+            .map(|pattern| self.decode(table, pattern))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Wiring {
+    /// `a->c b->f ...`, wire first
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (wire, &segment) in self.segments.iter().enumerate() {
+            if wire > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}->{}", name(wire), name(segment))?;
+        }
+        Ok(())
+    }
+}
+
+/// Backtracking search of wire to segment assignments
+struct Search<'a> {
+    table: &'a GlyphTable,
+    patterns: Vec<u32>,
+    // Segment of each wire assigned so far
+    assigned: Vec<usize>,
+    used: u32,
+    found: Vec<Wiring>,
+    limit: usize,
+}
+
+impl Search<'_> {
+    /// Can every pattern still become a symbol?
+    ///
+    /// The segments of assigned wires in a pattern must fit in a glyph of the
+    /// same size, which must not use the segments of assigned wires outside it.
+    fn feasible(&self) -> bool {
+        self.patterns.iter().all(|&pattern| {
+            let (inside, outside) = self.assigned.iter().enumerate().fold(
+                (0_u32, 0_u32),
+                |(inside, outside), (wire, &segment)| {
+                    if pattern & 1 << wire == 0 {
+                        (inside, outside | 1 << segment)
+                    } else {
+                        (inside | 1 << segment, outside)
+                    }
+                },
+            );
+            self.table.glyphs.iter().any(|&(_, glyph)| {
+                glyph.count_ones() == pattern.count_ones()
+                    && inside & !glyph == 0
+                    && outside & glyph == 0
+            })
+        })
+    }
+
+    fn run(&mut self) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        if self.assigned.len() == self.table.segments {
+            self.found.push(Wiring {
+                segments: self.assigned.clone(),
+            });
+            return;
+        }
+        for segment in 0..self.table.segments {
+            if self.used & 1 << segment != 0 {
+                continue;
+            }
+            self.assigned.push(segment);
+            self.used |= 1 << segment;
+            if self.feasible() {
+                self.run();
+            }
+            self.used &= !(1 << segment);
+            self.assigned.pop();
+        }
+    }
+}
+
+/// Up to `limit` wirings turning every pattern into a symbol of `table`
+///
+/// # Errors
+///
+/// Fails on an invalid wire
+pub fn wirings<T: AsRef<str>>(
+    table: &GlyphTable,
+    patterns: &[T],
+    limit: usize,
+) -> Result<Vec<Wiring>, WiringError> {
+    let mut patterns = patterns
+        .iter()
+        .map(|pattern| wire_set(pattern.as_ref(), table.segments))
+        .collect::<Result<Vec<_>, _>>()?;
+    patterns.sort_unstable();
+    patterns.dedup();
+
+    let mut search = Search {
+        table,
+        patterns,
+        assigned: Vec::with_capacity(table.segments),
+        used: 0,
+        found: Vec::new(),
+        limit,
+    };
+    search.run();
+    Ok(search.found)
+}
+
+/// The only wiring turning every pattern into a symbol of `table`. Any subset
+/// of the symbols may be observed, as long as it pins the wiring down.
+///
+/// # Errors
+///
+/// Fails on an invalid wire, if no wiring fits, or if several do
+pub fn solve<T: AsRef<str>>(table: &GlyphTable, patterns: &[T]) -> Result<Wiring, WiringError> {
+    let mut found = wirings(table, patterns, 2)?;
+    match found.len() {
+        0 => Err(WiringError::Inconsistent),
+        1 => Ok(found.remove(0)),
+        _ => Err(WiringError::Ambiguous),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATTERNS: [&str; 10] = [
+        "acedgfb", "cdfbe", "gcdfa", "fbcad", "dab", "cefabd", "cdfgeb", "eafb", "cagedb", "ab",
+    ];
+
+    /// Rename segments, `wires` giving the wire plugged to each segment
+    fn scramble(lit: &str, wires: &str) -> String {
+        lit.chars()
+            .map(|segment| {
+                let idx = wire_set(&segment.to_string(), wires.len()).unwrap();
+                wires.chars().nth(idx.trailing_zeros() as usize).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_solve() {
+        let table = GlyphTable::digits();
+        let wiring = solve(&table, &PATTERNS).unwrap();
+        assert_eq!(wiring.to_string(), "a->c b->f c->g d->a e->b f->d g->e");
+        assert_eq!(wiring.segment('d'), Some('a'));
+        assert_eq!(wiring.segment('z'), None);
+
+        assert_eq!(
+            wiring.read(&table, "cdfeb fcadb cdfeb cdbaf").unwrap(),
+            "5353"
+        );
+        assert_eq!(wiring.read(&table, "ab dab ab").unwrap(), "171");
+        assert_eq!(
+            wiring.decode(&table, "abc"),
+            Err(WiringError::UnknownPattern("abc".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_partial_observations() {
+        let table = GlyphTable::digits();
+        // Without the 1, the 4 and the 8, the other digits still pin the wiring down
+        let partial: Vec<_> = PATTERNS
+            .iter()
+            .filter(|pattern| ![2, 4, 7].contains(&pattern.len()))
+            .collect();
+        assert_eq!(solve(&table, &partial), solve(&table, &PATTERNS));
+
+        assert_eq!(solve(&table, &["ab", "dab"]), Err(WiringError::Ambiguous));
+        assert_eq!(
+            wirings(&table, &["ab", "dab", "eafb"], 100).unwrap().len(),
+            8
+        );
+        assert_eq!(
+            solve(&table, &["ab", "cde"]),
+            Err(WiringError::Inconsistent)
+        );
+        assert_eq!(solve(&table, &["ab", "cd"]), Err(WiringError::Inconsistent));
+        assert_eq!(solve(&table, &["xy"]), Err(WiringError::InvalidWire('x')));
+    }
+
+    #[test]
+    fn test_glyph_tables() {
+        // Hex digits, with the wiring of the example above
+        let table = GlyphTable::hex_digits();
+        let patterns: Vec<_> = DIGITS
+            .iter()
+            .chain(&HEX_LETTERS)
+            .map(|(_, lit)| scramble(lit, "deafgbc"))
+            .collect();
+        let wiring = solve(&table, &patterns).unwrap();
+        assert_eq!(wiring, solve(&GlyphTable::digits(), &PATTERNS).unwrap());
+        let reading = [&patterns[10], &patterns[7], &patterns[15], &patterns[11]];
+        assert_eq!(
+            wiring.read(&table, &reading.map(String::as_str).join(" ")),
+            Ok("A7Fb".to_string())
+        );
+
+        // Faulty displays: with `e` stuck on, a 5 looks like a 6
+        assert_eq!(
+            GlyphTable::digits().with_stuck("e", ""),
+            Err(WiringError::DuplicateGlyph('5', '6'))
+        );
+        let faulty = GlyphTable::digits().with_stuck("", "g").unwrap();
+        assert_eq!(faulty.symbol(wire_set("abcef", 7).unwrap()), Some('0'));
+
+        // A fourteen-segment display only showing a few letters: unused
+        // segments can be swapped, but every candidate reads the same
+        let letters = [
+            ('A', "abcefgk"),
+            ('K', "efgjl"),
+            ('M', "bcefhj"),
+            ('X', "hjlm"),
+            ('Z', "adjm"),
+        ];
+        let fourteen = GlyphTable::new(14, &letters).unwrap();
+        let wires = "nmlkjihgfedcba";
+        let patterns: Vec<_> = letters
+            .iter()
+            .map(|(_, lit)| scramble(lit, wires))
+            .collect();
+        assert_eq!(solve(&fourteen, &patterns), Err(WiringError::Ambiguous));
+        let reading = [&patterns[4], &patterns[0], &patterns[3]]
+            .map(String::as_str)
+            .join(" ");
+        let candidates = wirings(&fourteen, &patterns, 50).unwrap();
+        assert!(candidates.len() > 1);
+        for wiring in candidates {
+            assert_eq!(wiring.read(&fourteen, &reading), Ok("ZAX".to_string()));
+        }
+
+        assert_eq!(
+            GlyphTable::new(33, &[]),
+            Err(WiringError::InvalidSegmentCount(33))
+        );
+    }
+}
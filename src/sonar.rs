@@ -0,0 +1,328 @@
+use std::{collections::VecDeque, num::NonZeroUsize, ops::Range};
+
+/// How consecutive values evolve
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Trend {
+    pub increases: usize,
+    pub decreases: usize,
+    pub unchanged: usize,
+}
+
+impl Trend {
+    fn record(&mut self, previous: usize, current: usize) {
+        match current.cmp(&previous) {
+            std::cmp::Ordering::Greater => self.increases += 1,
+            std::cmp::Ordering::Less => self.decreases += 1,
+            std::cmp::Ordering::Equal => self.unchanged += 1,
+        }
+    }
+}
+
+/// Count increases, decreases and plateaus between consecutive values
+#[must_use]
+pub fn trend(values: &[usize]) -> Trend {
+    values.windows(2).fold(Trend::default(), |mut trend, pair| {
+        trend.record(pair[0], pair[1]);
+        trend
+    })
+}
+
+/// Sum of every window of `width` consecutive values
+#[must_use]
+pub fn window_sums(values: &[usize], width: NonZeroUsize) -> Vec<usize> {
+    values
+        .windows(width.get())
+        .map(|window| window.iter().sum())
+        .collect()
+}
+
+/// Mean of every window of `width` consecutive values
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn window_means(values: &[usize], width: NonZeroUsize) -> Vec<f64> {
+    window_sums(values, width)
+        .into_iter()
+        .map(|sum| sum as f64 / width.get() as f64)
+        .collect()
+}
+
+/// Median of every window of `width` consecutive values.
+///
+/// For even widths the median is the mean of the two middle values.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn window_medians(values: &[usize], width: NonZeroUsize) -> Vec<f64> {
+    let width = width.get();
+    if values.len() < width {
+        return Vec::new();
+    }
+
+    // Keep the current window sorted, swapping one value in and out per step
+    let mut sorted = values[..width].to_vec();
+    sorted.sort_unstable();
+
+    let median = |sorted: &[usize]| {
+        let mid = width / 2;
+        if width % 2 == 1 {
+            sorted[mid] as f64
+        } else {
+            // Sorted, so the difference cannot underflow and the sum cannot overflow
+            sorted[mid - 1] as f64 + (sorted[mid] - sorted[mid - 1]) as f64 / 2.0
+        }
+    };
+
+    let mut res = vec![median(&sorted)];
+    for (old, new) in values.iter().zip(values.iter().skip(width)) {
+        if let Ok(pos) = sorted.binary_search(old) {
+            sorted.remove(pos);
+        }
+        let pos = sorted.binary_search(new).unwrap_or_else(|pos| pos);
+        sorted.insert(pos, *new);
+        res.push(median(&sorted));
+    }
+    res
+}
+
+/// Longest run of strictly increasing values, as a range of indices.
+///
+/// Returns the earliest run on ties, and `None` for an empty input.
+#[must_use]
+pub fn longest_rising_run(values: &[usize]) -> Option<Range<usize>> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut best = 0..1;
+    let mut start = 0;
+    for idx in 1..values.len() {
+        if values[idx] <= values[idx - 1] {
+            start = idx;
+        }
+        if idx + 1 - start > best.len() {
+            best = start..idx + 1;
+        }
+    }
+    Some(best)
+}
+
+/// Indices of values further than `threshold` standard deviations from the mean
+#[must_use]
+pub fn outliers(values: &[usize], threshold: f64) -> Vec<usize> {
+    let mut stats = RunningStats::default();
+    for &value in values {
+        stats.push(value);
+    }
+
+    values
+        .iter()
+        .enumerate()
+        .filter(|&(_, &v)| stats.is_outlier(v, threshold))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Running mean and variance (Welford's algorithm)
+#[derive(Debug, Default, Clone, Copy)]
+struct RunningStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    #[allow(clippy::cast_precision_loss)]
+    fn push(&mut self, value: usize) {
+        let value = value as f64;
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn std_dev(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64).sqrt()
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn is_outlier(&self, value: usize, threshold: f64) -> bool {
+        let std_dev = self.std_dev();
+        std_dev > 0.0 && (value as f64 - self.mean).abs() > threshold * std_dev
+    }
+}
+
+/// Statistics gathered by a `SonarStream`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SonarSummary {
+    /// Number of depths read
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Trend between consecutive window sums
+    pub trend: Trend,
+    /// Longest strictly rising run of window sums, as a range of window
+    /// indices like `longest_rising_run` returns
+    pub longest_rising_run: Option<Range<usize>>,
+}
+
+/// Sonar analytics over a stream of depths, without keeping the sweep in memory.
+///
+/// Only the last `width` depths are kept around.
+#[derive(Debug, Clone)]
+pub struct SonarStream {
+    width: usize,
+    window: VecDeque<usize>,
+    sum: usize,
+    last_sum: Option<usize>,
+    stats: RunningStats,
+    trend: Trend,
+    windows: usize,
+    rising_start: usize,
+    longest_rising_run: Option<Range<usize>>,
+}
+
+impl SonarStream {
+    #[must_use]
+    pub fn new(width: NonZeroUsize) -> Self {
+        Self {
+            width: width.get(),
+            window: VecDeque::with_capacity(width.get()),
+            sum: 0,
+            last_sum: None,
+            stats: RunningStats::default(),
+            trend: Trend::default(),
+            windows: 0,
+            rising_start: 0,
+            longest_rising_run: None,
+        }
+    }
+
+    /// Read a depth. Returns the window sum once the window is full.
+    pub fn push(&mut self, depth: usize) -> Option<usize> {
+        self.stats.push(depth);
+        self.window.push_back(depth);
+        self.sum += depth;
+        if self.window.len() > self.width {
+            self.sum -= self.window.pop_front().unwrap_or(0);
+        }
+        if self.window.len() < self.width {
+            return None;
+        }
+
+        let idx = self.windows;
+        self.windows += 1;
+        match self.last_sum {
+            Some(last) if self.sum > last => {}
+            _ => self.rising_start = idx,
+        }
+        if let Some(last) = self.last_sum {
+            self.trend.record(last, self.sum);
+        }
+        let run = self.rising_start..self.windows;
+        if run.len()
+            > self
+                .longest_rising_run
+                .as_ref()
+                .map_or(0, ExactSizeIterator::len)
+        {
+            self.longest_rising_run = Some(run);
+        }
+        self.last_sum = Some(self.sum);
+        Some(self.sum)
+    }
+
+    /// Is `depth` further than `threshold` standard deviations from the depths read so far?
+    #[must_use]
+    pub fn is_outlier(&self, depth: usize, threshold: f64) -> bool {
+        self.stats.is_outlier(depth, threshold)
+    }
+
+    #[must_use]
+    pub fn summary(&self) -> SonarSummary {
+        SonarSummary {
+            count: self.stats.count,
+            mean: self.stats.mean,
+            std_dev: self.stats.std_dev(),
+            trend: self.trend,
+            longest_rising_run: self.longest_rising_run.clone(),
+        }
+    }
+}
+
+impl Extend<usize> for SonarStream {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for depth in iter {
+            self.push(depth);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWEEP: [usize; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn test_windows() {
+        let three = NonZeroUsize::new(3).unwrap();
+        let sums = window_sums(&SWEEP, three);
+        assert_eq!(sums, vec![607, 618, 618, 617, 647, 716, 769, 792]);
+        assert_eq!(
+            trend(&sums),
+            Trend {
+                increases: 5,
+                decreases: 1,
+                unchanged: 1
+            }
+        );
+        assert_eq!(
+            window_means(&[1, 2, 4, 8], three),
+            vec![7.0 / 3.0, 14.0 / 3.0]
+        );
+        assert_eq!(window_medians(&SWEEP, three)[..3], [200.0, 208.0, 208.0]);
+        assert_eq!(
+            window_medians(&[4, 1, 3, 2], NonZeroUsize::new(2).unwrap()),
+            vec![2.5, 2.0, 2.5]
+        );
+        assert_eq!(
+            window_medians(&[usize::MAX, usize::MAX - 2], NonZeroUsize::new(2).unwrap()),
+            vec![usize::MAX as f64]
+        );
+    }
+
+    #[test]
+    fn test_runs_and_outliers() {
+        assert_eq!(longest_rising_run(&SWEEP), Some(0..4));
+        assert_eq!(longest_rising_run(&[3, 2, 1]), Some(0..1));
+        assert_eq!(longest_rising_run(&[]), None);
+        assert_eq!(outliers(&[10, 11, 9, 10, 10, 11, 9, 10, 100], 2.0), vec![8]);
+    }
+
+    #[test]
+    fn test_stream_matches_slices() {
+        let mut stream = SonarStream::new(NonZeroUsize::new(3).unwrap());
+        stream.extend(SWEEP);
+        let summary = stream.summary();
+        assert_eq!(summary.count, 10);
+        assert_eq!(summary.trend.increases, 5);
+        assert_eq!(summary.trend.decreases, 1);
+        assert_eq!(summary.longest_rising_run, Some(3..8));
+        assert_eq!(
+            summary.longest_rising_run,
+            longest_rising_run(&window_sums(&SWEEP, NonZeroUsize::new(3).unwrap()))
+        );
+        assert_eq!(
+            SonarStream::new(NonZeroUsize::new(3).unwrap())
+                .summary()
+                .longest_rising_run,
+            None
+        );
+        assert!(stream.is_outlier(1000, 3.0));
+        assert!(!stream.is_outlier(230, 3.0));
+    }
+}
@@ -1,48 +1,223 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
+
+use thiserror::Error;
 
 /// Provides submarines direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Forward,
     Down,
     Up,
+    Back,
+    Surface,
+}
+
+impl Direction {
+    /// Does this direction expect a value after it?
+    #[must_use]
+    pub fn takes_value(self) -> bool {
+        self != Self::Surface
+    }
 }
 
 /// A Submarine command struct
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Command {
     pub direction: Direction,
     pub value: isize,
 }
 
+/// Errors raised while parsing a `Command`
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("empty command")]
+    Empty,
+    #[error("invalid direction `{0}`")]
+    InvalidDirection(String),
+    #[error("missing value after `{0}`")]
+    MissingValue(Direction),
+    #[error("invalid value `{0}`")]
+    InvalidValue(String),
+    #[error("unexpected token `{0}`")]
+    UnexpectedToken(String),
+}
+
 impl FromStr for Direction {
-    type Err = &'static str;
+    type Err = CommandError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "forward" => Ok(Self::Forward),
             "up" => Ok(Self::Up),
             "down" => Ok(Self::Down),
-            _ => Err("Invalid value"),
+            "back" => Ok(Self::Back),
+            "surface" => Ok(Self::Surface),
+            _ => Err(CommandError::InvalidDirection(s.to_string())),
         }
     }
 }
 
+impl Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Forward => "forward",
+            Self::Down => "down",
+            Self::Up => "up",
+            Self::Back => "back",
+            Self::Surface => "surface",
+        };
+        write!(f, "{s}")
+    }
+}
+
 impl FromStr for Command {
-    type Err = &'static str;
+    type Err = CommandError;
 
     /// Convert a String to Command
     ///
     /// # Errors
     ///
-    /// Fails if direction is invalid or value is not parsable
+    /// Fails if the line is empty, direction is invalid, value is missing or
+    /// not parsable, or if extra tokens follow the command
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s: Vec<_> = s.split_whitespace().collect();
-        let direction = s[0].parse::<Direction>();
-        let value = s[1].parse::<isize>();
-        match (direction, value) {
-            (Ok(direction), Ok(value)) => Ok(Command { direction, value }),
-            (Err(_), Err(_)) => Err("Both invalid"),
-            (Err(_), _) => Err("Invalid command"),
-            (_, Err(_)) => Err("Invalid value"),
+        let mut tokens = s.split_whitespace();
+        let direction = tokens
+            .next()
+            .ok_or(CommandError::Empty)?
+            .parse::<Direction>()?;
+
+        let value = if direction.takes_value() {
+            let token = tokens.next().ok_or(CommandError::MissingValue(direction))?;
+            token
+                .parse::<isize>()
+                .map_err(|_| CommandError::InvalidValue(token.to_string()))?
+        } else {
+            0
+        };
+
+        if let Some(extra) = tokens.next() {
+            return Err(CommandError::UnexpectedToken(extra.to_string()));
+        }
+
+        Ok(Command { direction, value })
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.direction.takes_value() {
+            write!(f, "{} {}", self.direction, self.value)
+        } else {
+            write!(f, "{}", self.direction)
+        }
+    }
+}
+
+/// Submarine state, updated by applying commands
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Submarine {
+    pub horizontal: isize,
+    pub depth: isize,
+    pub aim: isize,
+}
+
+impl Submarine {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a command where `up` and `down` change depth directly
+    pub fn apply(&mut self, command: &Command) {
+        match command.direction {
+            Direction::Forward => self.horizontal += command.value,
+            Direction::Back => self.horizontal -= command.value,
+            Direction::Down => self.depth += command.value,
+            Direction::Up => self.depth -= command.value,
+            Direction::Surface => self.depth = 0,
+        }
+    }
+
+    /// Apply a command where `up` and `down` change aim, and moving
+    /// changes depth along the aim. `surface` also levels the aim.
+    pub fn apply_with_aim(&mut self, command: &Command) {
+        match command.direction {
+            Direction::Forward => {
+                self.horizontal += command.value;
+                self.depth += command.value * self.aim;
+            }
+            Direction::Back => {
+                self.horizontal -= command.value;
+                self.depth -= command.value * self.aim;
+            }
+            Direction::Down => self.aim += command.value,
+            Direction::Up => self.aim -= command.value,
+            Direction::Surface => {
+                self.depth = 0;
+                self.aim = 0;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn product(&self) -> isize {
+        self.horizontal * self.depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_round_trip() {
+        for line in ["forward 5", "down -3", "up 0", "back 12", "surface"] {
+            let command = line.parse::<Command>().unwrap();
+            assert_eq!(command.to_string(), line);
         }
     }
+
+    #[test]
+    fn test_command_errors() {
+        assert_eq!("".parse::<Command>(), Err(CommandError::Empty));
+        assert_eq!(
+            "sideways 3".parse::<Command>(),
+            Err(CommandError::InvalidDirection("sideways".to_string()))
+        );
+        assert_eq!(
+            "forward".parse::<Command>(),
+            Err(CommandError::MissingValue(Direction::Forward))
+        );
+        assert_eq!(
+            "down x".parse::<Command>(),
+            Err(CommandError::InvalidValue("x".to_string()))
+        );
+        assert_eq!(
+            "up 3 4".parse::<Command>(),
+            Err(CommandError::UnexpectedToken("4".to_string()))
+        );
+        assert_eq!(
+            "surface 1".parse::<Command>(),
+            Err(CommandError::UnexpectedToken("1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_submarine_back_and_surface() {
+        let commands: Vec<Command> = ["forward 10", "down 4", "back 3", "surface", "down 2"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+
+        let mut submarine = Submarine::new();
+        commands.iter().for_each(|c| submarine.apply(c));
+        assert_eq!((submarine.horizontal, submarine.depth), (7, 2));
+
+        let mut submarine = Submarine::new();
+        commands.iter().for_each(|c| submarine.apply_with_aim(c));
+        assert_eq!(
+            (submarine.horizontal, submarine.depth, submarine.aim),
+            (7, 0, 2)
+        );
+    }
 }
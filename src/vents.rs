@@ -0,0 +1,867 @@
+use std::{fmt::Display, io::Write, str::FromStr};
+
+use hashbrown::HashMap;
+use thiserror::Error;
+
+use crate::Matrix2D;
+
+/// Errors raised while parsing vent lines
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum VentError {
+    #[error("invalid point `{0}`")]
+    InvalidPoint(String),
+    #[error("invalid segment `{0}`")]
+    InvalidSegment(String),
+}
+
+/// Errors raised while drawing a heatmap
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HeatmapError {
+    #[error("no vent to draw")]
+    NoVent,
+    #[error("a {width} by {height} map is too large to draw")]
+    TooLarge { width: usize, height: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    #[must_use]
+    pub fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+}
+
+impl FromStr for Point {
+    type Err = VentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || VentError::InvalidPoint(s.trim().to_string());
+        let (x, y) = s.split_once(',').ok_or_else(invalid)?;
+        let x = x.trim().parse().map_err(|_| invalid())?;
+        let y = y.trim().parse().map_err(|_| invalid())?;
+
+        Ok(Point::new(x, y))
+    }
+}
+
+/// Whether rasterisation includes the last endpoint of a segment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endpoints {
+    /// Both `a` and `b` are drawn
+    Inclusive,
+    /// `a` is drawn, `b` is not
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub a: Point,
+    pub b: Point,
+}
+
+impl FromStr for Segment {
+    type Err = VentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a, b) = s
+            .split_once("->")
+            .ok_or_else(|| VentError::InvalidSegment(s.trim().to_string()))?;
+
+        Ok(Segment::new(a.parse()?, b.parse()?))
+    }
+}
+
+fn gcd(a: isize, b: isize) -> isize {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn cross(u: (isize, isize), v: (isize, isize)) -> i128 {
+    u.0 as i128 * v.1 as i128 - u.1 as i128 * v.0 as i128
+}
+
+impl Segment {
+    #[must_use]
+    pub fn new(a: Point, b: Point) -> Self {
+        Self { a, b }
+    }
+
+    #[must_use]
+    pub fn is_horizontal(&self) -> bool {
+        self.a.y == self.b.y
+    }
+
+    #[must_use]
+    pub fn is_vertical(&self) -> bool {
+        self.a.x == self.b.x
+    }
+
+    #[must_use]
+    pub fn is_diagonal(&self) -> bool {
+        let d_y = self.a.y - self.b.y;
+        let d_x = self.a.x - self.b.x;
+        d_y.abs() == d_x.abs()
+    }
+
+    #[must_use]
+    pub fn is_horizontal_or_vertical(&self) -> bool {
+        self.is_horizontal() || self.is_vertical()
+    }
+
+    #[must_use]
+    pub fn is_horizontal_vertical_or_diagonal(&self) -> bool {
+        self.is_diagonal() || self.is_horizontal_or_vertical()
+    }
+
+    /// Every point of the segment, ends included, whatever its slope
+    #[must_use]
+    pub fn points(&self) -> Vec<Point> {
+        self.rasterise(Endpoints::Inclusive)
+    }
+
+    /// Points drawn from `a` to `b` with Bresenham's line algorithm.
+    ///
+    /// Horizontal, vertical and 45° segments give exactly their lattice
+    /// points; other slopes give the closest cells, one per step along the
+    /// major axis.
+    #[must_use]
+    pub fn rasterise(&self, endpoints: Endpoints) -> Vec<Point> {
+        let (d_x, d_y) = ((self.b.x - self.a.x).abs(), -(self.b.y - self.a.y).abs());
+        let (s_x, s_y) = (
+            if self.a.x < self.b.x { 1 } else { -1 },
+            if self.a.y < self.b.y { 1 } else { -1 },
+        );
+
+        let mut res = Vec::with_capacity(usize::try_from(d_x.max(-d_y)).unwrap_or(0) + 1);
+        let mut current = self.a;
+        let mut err = d_x + d_y;
+        loop {
+            if current == self.b {
+                if endpoints == Endpoints::Inclusive {
+                    res.push(current);
+                }
+                break;
+            }
+            res.push(current);
+            let e2 = 2 * err;
+            if e2 >= d_y {
+                err += d_y;
+                current.x += s_x;
+            }
+            if e2 <= d_x {
+                err += d_x;
+                current.y += s_y;
+            }
+        }
+        res
+    }
+
+    /// Lattice points the segment goes through, from `a` to `b`.
+    ///
+    /// The segment is walked one smallest lattice step at a time, so
+    /// `0,0 -> 4,2` goes through `0,0`, `2,1` and `4,2` only. These are the
+    /// points `VentSweepAndPrune` counts, while `rasterise` draws the closest
+    /// cell at every step. Both agree on horizontal, vertical and 45° segments.
+    #[must_use]
+    pub fn lattice_points(&self, endpoints: Endpoints) -> Vec<Point> {
+        let (d_x, d_y) = self.vector();
+        let steps = gcd(d_x, d_y);
+        let (s_x, s_y) = if steps == 0 {
+            (0, 0)
+        } else {
+            (d_x / steps, d_y / steps)
+        };
+        let last = match endpoints {
+            Endpoints::Inclusive => steps,
+            Endpoints::HalfOpen => steps - 1,
+        };
+        (0..=last)
+            .map(|k| Point::new(self.a.x + k * s_x, self.a.y + k * s_y))
+            .collect()
+    }
+
+    fn vector(&self) -> (isize, isize) {
+        (self.b.x - self.a.x, self.b.y - self.a.y)
+    }
+
+    /// Smallest lattice step along the segment line, pointing towards +x
+    /// (or +y for vertical lines). A single point is treated as horizontal.
+    #[must_use]
+    pub fn direction(&self) -> (isize, isize) {
+        let (d_x, d_y) = self.vector();
+        if d_x == 0 && d_y == 0 {
+            return (1, 0);
+        }
+        let g = gcd(d_x, d_y);
+        let (d_x, d_y) = (d_x / g, d_y / g);
+        if d_x < 0 || (d_x == 0 && d_y < 0) {
+            (-d_x, -d_y)
+        } else {
+            (d_x, d_y)
+        }
+    }
+
+    #[must_use]
+    pub fn x_range(&self) -> (isize, isize) {
+        (self.a.x.min(self.b.x), self.a.x.max(self.b.x))
+    }
+
+    #[must_use]
+    pub fn y_range(&self) -> (isize, isize) {
+        (self.a.y.min(self.b.y), self.a.y.max(self.b.y))
+    }
+
+    /// Does the segment (endpoints included) go through `p`?
+    #[must_use]
+    pub fn contains(&self, p: &Point) -> bool {
+        let (min_x, max_x) = self.x_range();
+        let (min_y, max_y) = self.y_range();
+        (min_x..=max_x).contains(&p.x)
+            && (min_y..=max_y).contains(&p.y)
+            && cross(self.vector(), (p.x - self.a.x, p.y - self.a.y)) == 0
+    }
+}
+
+/// Number of segments drawn on every point
+#[must_use]
+pub fn coverage(segments: &[Segment], endpoints: Endpoints) -> HashMap<Point, usize> {
+    let mut hmap: HashMap<Point, usize> = HashMap::new();
+    for point in segments.iter().flat_map(|s| s.rasterise(endpoints)) {
+        *hmap.entry(point).or_default() += 1;
+    }
+    hmap
+}
+
+/// Overlap counts over the bounding box of a set of vents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    /// Coordinates of the top left cell
+    pub origin: Point,
+    pub counts: Matrix2D<usize>,
+}
+
+impl Heatmap {
+    /// Most cells a map can have
+    pub const MAX_CELLS: usize = 1 << 24;
+
+    /// Rasterise the segments kept by `seg_condition`, on a frame fitting all
+    /// of `segments` so that filtered maps line up
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no vent, or if the frame has more than `MAX_CELLS` cells
+    pub fn new(
+        segments: &[Segment],
+        seg_condition: impl Fn(&Segment) -> bool,
+        endpoints: Endpoints,
+    ) -> Result<Self, HeatmapError> {
+        if segments.is_empty() {
+            return Err(HeatmapError::NoVent);
+        }
+        let min_x = segments.iter().map(|s| s.x_range().0).min().unwrap_or(0);
+        let max_x = segments.iter().map(|s| s.x_range().1).max().unwrap_or(0);
+        let min_y = segments.iter().map(|s| s.y_range().0).min().unwrap_or(0);
+        let max_y = segments.iter().map(|s| s.y_range().1).max().unwrap_or(0);
+
+        let width = max_x.abs_diff(min_x).saturating_add(1);
+        let height = max_y.abs_diff(min_y).saturating_add(1);
+        if width.saturating_mul(height) > Self::MAX_CELLS {
+            return Err(HeatmapError::TooLarge { width, height });
+        }
+        let mut counts = Matrix2D {
+            width,
+            height,
+            values: vec![0; width * height],
+        };
+        for point in segments
+            .iter()
+            .filter(|s| seg_condition(s))
+            .flat_map(|s| s.rasterise(endpoints))
+        {
+            let (x, y) = (point.x - min_x, point.y - min_y);
+            if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+                counts.values[x + y * width] += 1;
+            }
+        }
+
+        Ok(Self {
+            origin: Point::new(min_x, min_y),
+            counts,
+        })
+    }
+
+    /// Number of cells covered by at least two vents
+    #[must_use]
+    pub fn overlaps(&self) -> usize {
+        self.counts.values.iter().filter(|&&v| v >= 2).count()
+    }
+
+    /// Write the map as a binary greyscale PGM image, the most covered cells being white
+    ///
+    /// # Errors
+    ///
+    /// Fails if `writer` fails
+    pub fn write_pgm(&self, mut writer: impl Write) -> std::io::Result<()> {
+        let max = self.counts.values.iter().copied().max().unwrap_or(0).max(1);
+        writeln!(
+            writer,
+            "P5\n{} {}\n255",
+            self.counts.width, self.counts.height
+        )?;
+        let pixels: Vec<u8> = self
+            .counts
+            .values
+            .iter()
+            .map(|&v| u8::try_from(v * 255 / max).unwrap_or(u8::MAX))
+            .collect();
+        writer.write_all(&pixels)
+    }
+}
+
+impl Display for Heatmap {
+    /// Puzzle notation: `.` for no vent, the count otherwise (`+` above 9)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.counts.rows() {
+            let line: String = row
+                .iter()
+                .map(|&v| match v {
+                    0 => '.',
+                    1..=9 => char::from_digit(u32::try_from(v).unwrap_or(0), 10).unwrap_or('+'),
+                    _ => '+',
+                })
+                .collect();
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A point with exact rational coordinates `x / den`, `y / den`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RationalPoint {
+    pub x: i128,
+    pub y: i128,
+    pub den: i128,
+}
+
+impl RationalPoint {
+    fn new(x: i128, y: i128, den: i128) -> Self {
+        let sign = den.signum();
+        let (x, y, den) = (x * sign, y * sign, den * sign);
+        let g = [x, y].iter().fold(den, |g, &v| {
+            let (mut a, mut b) = (g.abs(), v.abs());
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+            a
+        });
+        Self {
+            x: x / g,
+            y: y / g,
+            den: den / g,
+        }
+    }
+
+    /// The lattice point, if both coordinates are integers
+    #[must_use]
+    pub fn to_point(&self) -> Option<Point> {
+        if self.den == 1 {
+            Some(Point::new(
+                isize::try_from(self.x).ok()?,
+                isize::try_from(self.y).ok()?,
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// A run of lattice points covered by at least two collinear segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overlap {
+    pub from: Point,
+    pub to: Point,
+    /// Number of lattice points in the run, ends included
+    pub len: usize,
+}
+
+/// Two non-parallel segments meeting at `point`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crossing {
+    pub segments: (usize, usize),
+    pub point: RationalPoint,
+}
+
+/// Everything found by a `VentSweepAndPrune`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Intersections {
+    pub overlaps: Vec<Overlap>,
+    pub crossings: Vec<Crossing>,
+    /// Number of distinct lattice points covered by at least two segments
+    pub points: usize,
+}
+
+type LineKey = (isize, isize, i128);
+
+/// Overlap runs along one line, as ranges of `dot(p, direction)`
+struct LineRuns {
+    direction: (isize, isize),
+    base: Point,
+    runs: Vec<(i128, i128)>,
+}
+
+impl LineRuns {
+    fn param(&self, p: &Point) -> i128 {
+        p.x as i128 * self.direction.0 as i128 + p.y as i128 * self.direction.1 as i128
+    }
+
+    fn step(&self) -> i128 {
+        let (d_x, d_y) = self.direction;
+        let (d_x, d_y) = (d_x as i128, d_y as i128);
+        d_x * d_x + d_y * d_y
+    }
+
+    fn point_at(&self, t: i128) -> Point {
+        let k = isize::try_from((t - self.param(&self.base)) / self.step()).unwrap_or(0);
+        Point::new(
+            self.base.x + k * self.direction.0,
+            self.base.y + k * self.direction.1,
+        )
+    }
+
+    fn covers(&self, p: &Point) -> bool {
+        let t = self.param(p);
+        let idx = self.runs.partition_point(|&(start, _)| start <= t);
+        idx > 0 && self.runs[idx - 1].1 >= t
+    }
+}
+
+/// Intersection finder over a set of segments of any slope.
+///
+/// Sweep and prune: segments are sorted by their leftmost x, and a sweep from
+/// left to right only compares segments whose x ranges overlap. This is
+/// O(n log n) when few x ranges overlap, but every pair is still tested when
+/// they all do, so the worst case is O(n²). Collinear segments are grouped by
+/// line and merged with a one dimensional sweep.
+pub struct VentSweepAndPrune<'a> {
+    segments: &'a [Segment],
+    order: Vec<usize>,
+}
+
+impl<'a> VentSweepAndPrune<'a> {
+    #[must_use]
+    pub fn new(segments: &'a [Segment]) -> Self {
+        let mut order: Vec<usize> = (0..segments.len()).collect();
+        order.sort_by_key(|&idx| segments[idx].x_range().0);
+        Self { segments, order }
+    }
+
+    fn line_key(segment: &Segment) -> LineKey {
+        let (d_x, d_y) = segment.direction();
+        (d_x, d_y, cross((d_x, d_y), (segment.a.x, segment.a.y)))
+    }
+
+    /// Find collinear overlaps and crossings between all segments
+    #[must_use]
+    pub fn intersections(&self) -> Intersections {
+        // Group collinear segments
+        let mut line_ids: HashMap<LineKey, usize> = HashMap::new();
+        let mut lines: Vec<Vec<usize>> = Vec::new();
+        let line_of: Vec<usize> = self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(idx, segment)| {
+                let id = *line_ids.entry(Self::line_key(segment)).or_insert_with(|| {
+                    lines.push(Vec::new());
+                    lines.len() - 1
+                });
+                lines[id].push(idx);
+                id
+            })
+            .collect();
+
+        let runs: Vec<LineRuns> = lines.iter().map(|line| self.line_runs(line)).collect();
+
+        let mut res = Intersections::default();
+        for line in &runs {
+            for &(start, end) in &line.runs {
+                res.overlaps.push(Overlap {
+                    from: line.point_at(start),
+                    to: line.point_at(end),
+                    len: usize::try_from((end - start) / line.step()).unwrap_or(0) + 1,
+                });
+            }
+        }
+        res.points = res.overlaps.iter().map(|overlap| overlap.len).sum();
+
+        // Sweep from left to right, testing each segment against the active ones
+        let mut active: Vec<usize> = Vec::new();
+        for &idx in &self.order {
+            let segment = &self.segments[idx];
+            let (min_x, _) = segment.x_range();
+            active.retain(|&other| self.segments[other].x_range().1 >= min_x);
+
+            for &other in &active {
+                if line_of[other] == line_of[idx] {
+                    continue;
+                }
+                if let Some(point) = crossing(&self.segments[other], segment) {
+                    res.crossings.push(Crossing {
+                        segments: (other.min(idx), other.max(idx)),
+                        point,
+                    });
+                }
+            }
+            active.push(idx);
+        }
+        res.crossings.sort_by_key(|crossing| crossing.segments);
+
+        // Every lattice crossing point has to be counted once: add the ones
+        // outside of any run, and remove duplicates where runs of different
+        // lines cross each other
+        let mut crossing_lines: HashMap<Point, Vec<usize>> = HashMap::new();
+        for crossing in &res.crossings {
+            if let Some(point) = crossing.point.to_point() {
+                let entry = crossing_lines.entry(point).or_default();
+                entry.push(line_of[crossing.segments.0]);
+                entry.push(line_of[crossing.segments.1]);
+            }
+        }
+        for (point, line_ids) in &mut crossing_lines {
+            line_ids.sort_unstable();
+            line_ids.dedup();
+            let in_runs = line_ids
+                .iter()
+                .filter(|&&id| runs[id].covers(point))
+                .count();
+            if in_runs == 0 {
+                res.points += 1;
+            } else {
+                res.points -= in_runs - 1;
+            }
+        }
+
+        res
+    }
+
+    fn line_runs(&self, line: &[usize]) -> LineRuns {
+        let first = &self.segments[line[0]];
+        let mut runs = LineRuns {
+            direction: first.direction(),
+            base: first.a,
+            runs: Vec::new(),
+        };
+
+        // Closed intervals: starts are handled before ends at the same place
+        let mut events: Vec<(i128, i8)> = line
+            .iter()
+            .flat_map(|&idx| {
+                let segment = &self.segments[idx];
+                let (t_a, t_b) = (runs.param(&segment.a), runs.param(&segment.b));
+                [(t_a.min(t_b), -1), (t_a.max(t_b), 1)]
+            })
+            .collect();
+        events.sort_unstable();
+
+        let mut coverage = 0;
+        let mut start = 0;
+        for (t, kind) in events {
+            if kind < 0 {
+                coverage += 1;
+                if coverage == 2 {
+                    start = t;
+                }
+            } else {
+                if coverage == 2 {
+                    runs.runs.push((start, t));
+                }
+                coverage -= 1;
+            }
+        }
+        runs
+    }
+
+    /// Indices of the segments going through `p`
+    #[must_use]
+    pub fn covering(&self, p: &Point) -> Vec<usize> {
+        let end = self
+            .order
+            .partition_point(|&idx| self.segments[idx].x_range().0 <= p.x);
+        let mut res: Vec<usize> = self.order[..end]
+            .iter()
+            .copied()
+            .filter(|&idx| self.segments[idx].contains(p))
+            .collect();
+        res.sort_unstable();
+        res
+    }
+}
+
+/// Exact meeting point of two non-parallel segments
+fn crossing(first: &Segment, second: &Segment) -> Option<RationalPoint> {
+    let (u, v) = (first.vector(), second.vector());
+    // A single point segment only meets others where it lies
+    if u == (0, 0) {
+        return second
+            .contains(&first.a)
+            .then(|| RationalPoint::new(first.a.x as i128, first.a.y as i128, 1));
+    }
+    if v == (0, 0) {
+        return first
+            .contains(&second.a)
+            .then(|| RationalPoint::new(second.a.x as i128, second.a.y as i128, 1));
+    }
+
+    let (min_y, max_y) = first.y_range();
+    let (other_min_y, other_max_y) = second.y_range();
+    if max_y < other_min_y || other_max_y < min_y {
+        return None;
+    }
+
+    let denom = cross(u, v);
+    if denom == 0 {
+        return None;
+    }
+    let w = (second.a.x - first.a.x, second.a.y - first.a.y);
+    // first.a + s * u == second.a + t * v, with s = s_num / denom and t = t_num / denom
+    let (s_num, t_num) = (cross(w, v), cross(w, u));
+    let within = |num: i128| {
+        if denom > 0 {
+            (0..=denom).contains(&num)
+        } else {
+            (denom..=0).contains(&num)
+        }
+    };
+    if !within(s_num) || !within(t_num) {
+        return None;
+    }
+
+    let (a_x, a_y) = (first.a.x as i128, first.a.y as i128);
+    Some(RationalPoint::new(
+        a_x * denom + s_num * u.0 as i128,
+        a_y * denom + s_num * u.1 as i128,
+        denom,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(input: &str) -> Vec<Segment> {
+        input.lines().map(|l| l.parse().unwrap()).collect()
+    }
+
+    fn lattice_count(segments: &[Segment]) -> usize {
+        let mut hmap: HashMap<Point, usize> = HashMap::new();
+        for point in segments
+            .iter()
+            .flat_map(|s| s.lattice_points(Endpoints::Inclusive))
+        {
+            *hmap.entry(point).or_default() += 1;
+        }
+        hmap.values().filter(|&&v| v >= 2).count()
+    }
+
+    const VENTS: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
+
+    #[test]
+    fn test_sweep_matches_lattice_points() {
+        let all = segments(VENTS);
+        let straight: Vec<_> = all
+            .iter()
+            .filter(|s| s.is_horizontal_or_vertical())
+            .copied()
+            .collect();
+
+        assert_eq!(VentSweepAndPrune::new(&straight).intersections().points, 5);
+        assert_eq!(VentSweepAndPrune::new(&all).intersections().points, 12);
+        assert_eq!(lattice_count(&all), 12);
+
+        let tricky = segments(
+            "0,0 -> 4,0\n2,0 -> 6,0\n4,0 -> 4,4\n3,-1 -> 5,1\n0,4 -> 4,0\n9,9 -> 9,9\n9,7 -> 9,10",
+        );
+        assert_eq!(
+            VentSweepAndPrune::new(&tricky).intersections().points,
+            lattice_count(&tricky)
+        );
+
+        // Slopes other than 45°, crossing on and off lattice points
+        let skewed =
+            segments("0,0 -> 4,2\n0,1 -> 4,1\n2,0 -> 2,3\n0,3 -> 6,0\n4,2 -> 8,4\n1,0 -> 3,3");
+        assert_eq!(lattice_count(&skewed), 4);
+        assert_eq!(VentSweepAndPrune::new(&skewed).intersections().points, 4);
+    }
+
+    #[test]
+    fn test_overlaps_and_crossings() {
+        let vents = segments("0,0 -> 4,0\n6,0 -> 2,0\n0,0 -> 2,2\n0,2 -> 2,0");
+        let res = VentSweepAndPrune::new(&vents).intersections();
+
+        assert_eq!(
+            res.overlaps,
+            vec![Overlap {
+                from: Point::new(2, 0),
+                to: Point::new(4, 0),
+                len: 3
+            }]
+        );
+        assert!(res.crossings.contains(&Crossing {
+            segments: (2, 3),
+            point: RationalPoint { x: 1, y: 1, den: 1 }
+        }));
+        assert_eq!(res.points, 5);
+
+        let crossing_runs = segments("0,2 -> 4,2\n1,2 -> 3,2\n2,0 -> 2,4\n2,1 -> 2,3");
+        let res = VentSweepAndPrune::new(&crossing_runs).intersections();
+        assert_eq!(res.overlaps.len(), 2);
+        assert_eq!(res.points, lattice_count(&crossing_runs));
+
+        let skewed = segments("0,0 -> 1,1\n0,1 -> 1,0");
+        let res = VentSweepAndPrune::new(&skewed).intersections();
+        assert_eq!(res.crossings[0].point, RationalPoint { x: 1, y: 1, den: 2 });
+        assert_eq!(res.points, 0);
+    }
+
+    #[test]
+    fn test_rasterise() {
+        let segment: Segment = "0,0 -> 4,2".parse().unwrap();
+        assert_eq!(
+            segment.points(),
+            vec![
+                Point::new(0, 0),
+                Point::new(1, 1),
+                Point::new(2, 1),
+                Point::new(3, 2),
+                Point::new(4, 2)
+            ]
+        );
+        let shallow: Segment = "0,0 -> 5,1".parse().unwrap();
+        assert_eq!(shallow.points().len(), 6);
+        let steep: Segment = "1,3 -> 0,0".parse().unwrap();
+        assert_eq!(
+            steep.rasterise(Endpoints::HalfOpen),
+            vec![Point::new(1, 3), Point::new(1, 2), Point::new(0, 1)]
+        );
+
+        assert_eq!(
+            segment.lattice_points(Endpoints::Inclusive),
+            vec![Point::new(0, 0), Point::new(2, 1), Point::new(4, 2)]
+        );
+        let steep: Segment = "2,6 -> 0,0".parse().unwrap();
+        assert_eq!(
+            steep.lattice_points(Endpoints::HalfOpen),
+            vec![Point::new(2, 6), Point::new(1, 3)]
+        );
+        let diagonal: Segment = "8,0 -> 5,3".parse().unwrap();
+        assert_eq!(
+            diagonal.lattice_points(Endpoints::Inclusive),
+            diagonal.points()
+        );
+        assert_eq!(
+            diagonal.points(),
+            vec![
+                Point::new(8, 0),
+                Point::new(7, 1),
+                Point::new(6, 2),
+                Point::new(5, 3)
+            ]
+        );
+
+        let single: Segment = "2,2 -> 2,2".parse().unwrap();
+        assert_eq!(single.points(), vec![Point::new(2, 2)]);
+        assert!(single.rasterise(Endpoints::HalfOpen).is_empty());
+        assert!(single.lattice_points(Endpoints::HalfOpen).is_empty());
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let vents = segments(VENTS);
+        let heatmap = Heatmap::new(
+            &vents,
+            Segment::is_horizontal_or_vertical,
+            Endpoints::Inclusive,
+        )
+        .unwrap();
+        assert_eq!(heatmap.overlaps(), 5);
+        assert_eq!(
+            heatmap.to_string(),
+            ".......1..
+..1....1..
+..1....1..
+.......1..
+.112111211
+..........
+..........
+..........
+..........
+222111....
+"
+        );
+
+        let diagonals = Heatmap::new(
+            &vents,
+            |s| !s.is_horizontal_or_vertical(),
+            Endpoints::Inclusive,
+        )
+        .unwrap();
+        assert_eq!(diagonals.origin, heatmap.origin);
+        assert_eq!(diagonals.counts.get_x_y(4, 4), 2);
+
+        let mut pgm = Vec::new();
+        heatmap.write_pgm(&mut pgm).unwrap();
+        assert!(pgm.starts_with(b"P5\n10 10\n255\n"));
+        assert_eq!(pgm.len(), 13 + 100);
+        assert_eq!(pgm[13 + 4 * 10 + 3], 255);
+        assert_eq!(pgm[13 + 4 * 10 + 2], 127);
+
+        assert_eq!(
+            Heatmap::new(&[], |_| true, Endpoints::Inclusive),
+            Err(HeatmapError::NoVent)
+        );
+        assert_eq!(
+            Heatmap::new(
+                &segments("0,0 -> 0,0\n1000000000,0 -> 1000000000,0"),
+                |_| true,
+                Endpoints::Inclusive
+            ),
+            Err(HeatmapError::TooLarge {
+                width: 1_000_000_001,
+                height: 1
+            })
+        );
+        assert!(Heatmap::new(
+            &segments(&format!("{},{} -> {},{}", isize::MIN, 0, isize::MAX, 0)),
+            |_| true,
+            Endpoints::Inclusive
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_covering() {
+        let vents = segments(VENTS);
+        let sweep = VentSweepAndPrune::new(&vents);
+        assert_eq!(sweep.covering(&Point::new(7, 4)), vec![2, 4]);
+        assert_eq!(sweep.covering(&Point::new(4, 4)), vec![1, 2, 8]);
+        assert!(sweep.covering(&Point::new(9, 9)).is_empty());
+    }
+}